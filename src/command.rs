@@ -0,0 +1,395 @@
+use eksicli::export::Format;
+use eksicli::endpoints::entry::Spoilers;
+use eksicli::endpoints::channel::Channel;
+use eksicli::endpoints::title::EntryFilter;
+
+/// Commands that can be typed into the command line
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// `/query`
+    Search(String),
+    /// `:page <n>`, 1-based
+    Page(usize),
+    /// `:author <nick>`
+    Author(String),
+    /// `:entry <n>`, 1-based index of the loaded entries
+    Entry(usize),
+    /// `:goto <n>`, the ordinal of the entry in the title
+    Goto(usize),
+    /// `:date YYYY[-MM[-DD]]`, the first entry of the title written on or
+    /// after the day
+    Date(String),
+    /// `:sort all|popular|nice|dailynice|links|images`, `:by <nick>` and
+    /// `:grep <keywords>`
+    Sort(EntryFilter),
+    /// `:export [md|txt] [keep|strip|mark]`
+    Export(Format, Spoilers),
+    /// `:find [text]`, finds the text in the loaded entries, clears the
+    /// highlighting without it
+    Find(String),
+    /// `:open <url or title>`
+    Open(String),
+    /// `:link <n>`, opens the `[n]` link of the current entry
+    OpenLink(usize),
+    /// `:yank [n]`, copies the `[n]` link or the url of the current entry
+    Yank(Option<usize>),
+    /// `:q`
+    Quit,
+    /// `:search`, opens the detailed search form
+    DetailedSearch,
+    /// `:channel <name>`, like `gundem` or `#spor`
+    Channel(Channel),
+    /// `:debe`
+    Debe,
+    Block(String),
+    Unblock(String),
+    Mute(String),
+    Unmute(String),
+    Hide(String),
+    Unhide(String),
+    Filter(bool),
+}
+
+/// Names, arguments and help of the `:` commands, used for completion and
+/// the help
+pub const COMMANDS: &[(&str, &str, &str)] = &[
+    ("author", "<nick>", "yazarin entry'leri"),
+    ("block", "<nick>", "yazari engelle"),
+    ("by", "<nick>", "yazarin bu basliktaki entry'leri"),
+    ("channel", "<kanal>", "kanala gec"),
+    ("date", "<yyyy[-aa[-gg]]>", "o tarihteki ya da sonraki ilk entry'ye git"),
+    ("debe", "", "dunun en begenilen entry'leri"),
+    ("entry", "<n>", "yuklu n. entry'ye git"),
+    ("export", "[md|txt] [keep|strip|mark]", "basligi disa aktar"),
+    ("filter", "[on|off]", "filtreleri ac/kapat"),
+    ("find", "[metin]", "yuklu entry'lerde bul"),
+    ("goto", "<n>", "basligin n. entry'sine git"),
+    ("grep", "<kelimeler>", "baslikta ara"),
+    ("hide", "<desen>", "eslesen basliklari gizle"),
+    ("link", "<n>", "[n] linkini ac"),
+    ("mute", "<kelime>", "kelimeyi iceren entry'leri gizle"),
+    ("open", "<url|baslik>", "ac"),
+    ("page", "<n>", "sayfaya git"),
+    ("q", "", "cik"),
+    ("quit", "", "cik"),
+    ("search", "", "detayli arama"),
+    ("sort", "[all|popular|nice|dailynice|links|images]", "entry'leri sirala, all varsayilan"),
+    ("unblock", "<nick>", "engeli kaldir"),
+    ("unhide", "<desen>", "gizlemeyi kaldir"),
+    ("unmute", "<kelime>", "susturmayi kaldir"),
+    ("yank", "[n]", "[n] linkini ya da entry'yi kopyala"),
+];
+
+impl Command {
+    /// Parses given command line, which starts with either `/` or `:`
+    pub fn parse(input: &str) -> Result<Command, String> {
+        let mut chars = input.chars();
+        match chars.next() {
+            Some('/') => {
+                let query = chars.as_str().trim();
+                if query.is_empty() {
+                    Err("Search query is empty.".to_string())
+                } else {
+                    Ok(Command::Search(query.to_string()))
+                }
+            }
+            Some(':') => Command::parse_command(chars.as_str().trim()),
+            _ => Err(format!("Not a command: {}", input)),
+        }
+    }
+
+    fn parse_command(input: &str) -> Result<Command, String> {
+        let (name, arg) = match input.find(' ') {
+            Some(i) => (&input[..i], input[i + 1..].trim()),
+            None => (input, ""),
+        };
+
+        let require = |what: &str| -> Result<String, String> {
+            if arg.is_empty() {
+                Err(format!("{}: missing {}", name, what))
+            } else {
+                Ok(arg.to_string())
+            }
+        };
+        let index = || -> Result<usize, String> {
+            arg.trim_matches(|x| x == '[' || x == ']')
+                .parse::<usize>()
+                .map_err(|_| format!("{}: expected a link number, got '{}'", name, arg))
+        };
+        let number = || -> Result<usize, String> {
+            match arg.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(format!("{}: expected a positive number, got '{}'", name, arg)),
+            }
+        };
+
+        match name {
+            "page" => number().map(Command::Page),
+            "entry" => number().map(Command::Entry),
+            "goto" => number().map(Command::Goto),
+            "date" => {
+                let date = require("date")?;
+                parse_day(&date)
+                    .map(Command::Date)
+                    .ok_or_else(|| format!("date: expected YYYY-MM-DD, got '{}'", date))
+            },
+            "author" => require("nick").map(|x| Command::Author(x.trim_left_matches('@').to_string())),
+            "sort" => match arg {
+                "" => Ok(Command::Sort(EntryFilter::All)),
+                _ => EntryFilter::from_name(arg)
+                    .map(Command::Sort)
+                    .ok_or_else(|| format!(
+                        "sort: unknown order '{}' (all, popular, nice, dailynice, links, images)",
+                        arg
+                    )),
+            },
+            "export" => {
+                let mut format: Option<Format> = None;
+                let mut spoilers: Option<Spoilers> = None;
+                for x in arg.split_whitespace() {
+                    match (x.parse(), x.parse()) {
+                        (Ok(x), _) if format.is_none() => format = Some(x),
+                        (_, Ok(x)) if spoilers.is_none() => spoilers = Some(x),
+                        // Only the missing one can be meant
+                        (Err(e), _) if format.is_none() && spoilers.is_some() => return Err(format!("export: {}", e)),
+                        (_, Err(e)) if spoilers.is_none() && format.is_some() => return Err(format!("export: {}", e)),
+                        _ => return Err(format!("export: unknown argument '{}' (md, txt, keep, strip, mark, collapse)", x)),
+                    }
+                }
+
+                Ok(Command::Export(format.unwrap_or(Format::Markdown), spoilers.unwrap_or(Spoilers::Keep)))
+            },
+            "by" => require("nick").map(|x| Command::Sort(EntryFilter::Author(x.trim_left_matches('@').to_string()))),
+            "grep" => require("keywords").map(|x| Command::Sort(EntryFilter::Search(x))),
+            "find" => Ok(Command::Find(arg.to_string())),
+            "open" => require("url").map(Command::Open),
+            "link" => index().map(Command::OpenLink),
+            "yank" => match arg {
+                "" => Ok(Command::Yank(None)),
+                _ => index().map(|x| Command::Yank(Some(x))),
+            },
+            "q" | "quit" => Ok(Command::Quit),
+            "search" => Ok(Command::DetailedSearch),
+            "debe" => Ok(Command::Debe),
+            "channel" => {
+                let name = require("channel name")?;
+                Channel::from_name(&name)
+                    .map(Command::Channel)
+                    .ok_or_else(|| format!("channel: unknown channel '{}'", name))
+            },
+            "block" => require("author").map(Command::Block),
+            "unblock" => require("author").map(Command::Unblock),
+            "mute" => require("keyword").map(Command::Mute),
+            "unmute" => require("keyword").map(Command::Unmute),
+            "hide" => require("pattern").map(Command::Hide),
+            "unhide" => require("pattern").map(Command::Unhide),
+            "filter" => match arg {
+                "on" | "" => Ok(Command::Filter(true)),
+                "off" => Ok(Command::Filter(false)),
+                _ => Err(format!("filter: expected on or off, got '{}'", arg)),
+            },
+            "" => Err("Empty command.".to_string()),
+            _ => Err(format!("Unknown command: {}", name)),
+        }
+    }
+}
+
+/// Parses `YYYY`, `YYYY-MM`, `YYYY-MM-DD` or `DD.MM.YYYY` into `YYYY-MM-DD`,
+/// missing parts are the first month/day. `-`, `.` and `/` all separate the
+/// parts.
+pub fn parse_day(date: &str) -> Option<String> {
+    let parts: Vec<&str> = date.split(|x| x == '-' || x == '.' || x == '/').collect();
+    let numbers = parts.iter()
+        .map(|x| x.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let (year, month, day) = match numbers.len() {
+        1 if parts[0].len() == 4 => (numbers[0], 1, 1),
+        2 if parts[0].len() == 4 => (numbers[0], numbers[1], 1),
+        3 if parts[0].len() == 4 => (numbers[0], numbers[1], numbers[2]),
+        3 if parts[2].len() == 4 => (numbers[2], numbers[1], numbers[0]),
+        _ => return None,
+    };
+
+    if year < 1999 || month == 0 || month > 12 || day == 0 || day > 31 {
+        return None;
+    }
+
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+/// Returns the possible completions of given command line. Command names are
+/// completed after `:`, titles are completed after `/` and `:open`, channels
+/// are completed after `:channel`.
+pub fn complete(input: &str, titles: &[&str]) -> Vec<String> {
+    let starts_with = |x: &str, prefix: &str| x.to_lowercase().starts_with(&prefix.to_lowercase());
+
+    if input.starts_with('/') {
+        let prefix = &input[1..];
+        return titles.iter()
+            .filter(|x| starts_with(**x, prefix))
+            .map(|x| format!("/{}", x))
+            .collect();
+    }
+
+    if !input.starts_with(':') {
+        return vec![];
+    }
+
+    let input = &input[1..];
+    match input.find(' ') {
+        None => COMMANDS.iter()
+            .map(|x| x.0)
+            .filter(|x| x.starts_with(input))
+            .map(|x| format!(":{} ", x))
+            .collect(),
+        Some(i) if &input[..i] == "open" => {
+            let prefix = input[i + 1..].trim_left();
+            titles.iter()
+                .filter(|x| starts_with(**x, prefix))
+                .map(|x| format!(":open {}", x))
+                .collect()
+        }
+        Some(i) if &input[..i] == "channel" => {
+            let prefix = input[i + 1..].trim_left();
+            Channel::all()
+                .into_iter()
+                .map(|x| x.to_string())
+                .filter(|x| starts_with(x, prefix))
+                .map(|x| format!(":channel {}", x))
+                .collect()
+        }
+        Some(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses() {
+        let cases = vec![
+            ("/rust", Command::Search("rust".to_string())),
+            ("/  ekşi sözlük ", Command::Search("ekşi sözlük".to_string())),
+            (":page 3", Command::Page(3)),
+            (":entry 1", Command::Entry(1)),
+            (":goto 120", Command::Goto(120)),
+            (":date 2015", Command::Date("2015-01-01".to_string())),
+            (":date 2015-6-1", Command::Date("2015-06-01".to_string())),
+            (":author @ssg", Command::Author("ssg".to_string())),
+            (":sort", Command::Sort(EntryFilter::All)),
+            (":sort nice", Command::Sort(EntryFilter::Nice)),
+            (":sort images", Command::Sort(EntryFilter::Images)),
+            (":by ssg", Command::Sort(EntryFilter::Author("ssg".to_string()))),
+            (":grep iki kelime", Command::Sort(EntryFilter::Search("iki kelime".to_string()))),
+            (":export", Command::Export(Format::Markdown, Spoilers::Keep)),
+            (":export txt", Command::Export(Format::Text, Spoilers::Keep)),
+            (":export strip", Command::Export(Format::Markdown, Spoilers::Strip)),
+            (":export mark txt", Command::Export(Format::Text, Spoilers::Mark)),
+            (":find", Command::Find(String::new())),
+            (":find  ışık ", Command::Find("ışık".to_string())),
+            (":open https://eksisozluk.com/rust--1", Command::Open("https://eksisozluk.com/rust--1".to_string())),
+            (":link [2]", Command::OpenLink(2)),
+            (":link 0", Command::OpenLink(0)),
+            (":yank", Command::Yank(None)),
+            (":yank 1", Command::Yank(Some(1))),
+            (":q", Command::Quit),
+            (":quit", Command::Quit),
+            (":search", Command::DetailedSearch),
+            (":debe", Command::Debe),
+            (":channel #spor", Command::Channel(Channel::Kanal("spor".to_string()))),
+            (":channel Gündem", Command::Channel(Channel::Gundem)),
+            (":block ssg", Command::Block("ssg".to_string())),
+            (":unblock ssg", Command::Unblock("ssg".to_string())),
+            (":mute spoiler", Command::Mute("spoiler".to_string())),
+            (":unmute spoiler", Command::Unmute("spoiler".to_string())),
+            (":hide *futbol*", Command::Hide("*futbol*".to_string())),
+            (":unhide *futbol*", Command::Unhide("*futbol*".to_string())),
+            (":filter", Command::Filter(true)),
+            (":filter off", Command::Filter(false)),
+        ];
+
+        for (input, command) in cases {
+            assert_eq!(Command::parse(input), Ok(command), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects() {
+        let cases = &[
+            ("rust", "Not a command: rust"),
+            ("/  ", "Search query is empty."),
+            (":", "Empty command."),
+            (":nope", "Unknown command: nope"),
+            (":page", "page: expected a positive number, got ''"),
+            (":page 0", "page: expected a positive number, got '0'"),
+            (":goto -1", "goto: expected a positive number, got '-1'"),
+            (":date", "date: missing date"),
+            (":date dun", "date: expected YYYY-MM-DD, got 'dun'"),
+            (":author", "author: missing nick"),
+            (":sort eski", "sort: unknown order 'eski' (all, popular, nice, dailynice, links, images)"),
+            (":export pdf", "export: unknown argument 'pdf' (md, txt, keep, strip, mark, collapse)"),
+            // The argument that's still missing is reported
+            (":export md strp", "export: Unknown spoiler mode: strp (keep, strip, mark, collapse)"),
+            (":export strip mdd", "export: Unknown export format: mdd (md, txt)"),
+            (":link x", "link: expected a link number, got 'x'"),
+            (":yank -", "yank: expected a link number, got '-'"),
+            (":channel", "channel: missing channel name"),
+            (":channel yok", "channel: unknown channel 'yok'"),
+            (":mute", "mute: missing keyword"),
+            (":filter belki", "filter: expected on or off, got 'belki'"),
+        ];
+
+        for &(input, error) in cases {
+            assert_eq!(Command::parse(input), Err(error.to_string()), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn parses_days() {
+        let cases = &[
+            ("2015", Some("2015-01-01")),
+            ("2015-06", Some("2015-06-01")),
+            ("2015-06-09", Some("2015-06-09")),
+            ("1999-2-15", Some("1999-02-15")),
+            ("1998", None),
+            ("2015-13", None),
+            ("2015-00-10", None),
+            ("2015-06-32", None),
+            ("2015-06-09-1", None),
+            ("2015--09", None),
+            ("01.06.2015", Some("2015-06-01")),
+            ("9/6/2015", Some("2015-06-09")),
+            ("2015.06.09", Some("2015-06-09")),
+            ("01.06.15", None),
+            ("01.06", None),
+            ("15", None),
+            ("+2015", None),
+            ("", None),
+        ];
+
+        for &(date, day) in cases {
+            assert_eq!(parse_day(date), day.map(|x| x.to_string()), "{:?}", date);
+        }
+    }
+
+    #[test]
+    fn completes() {
+        let titles = &["rust", "Rust programlama dili", "go"];
+        let cases: &[(&str, &[&str])] = &[
+            (":de", &[":debe "]),
+            (":un", &[":unblock ", ":unhide ", ":unmute "]),
+            (":q", &[":q ", ":quit "]),
+            ("/ru", &["/rust", "/Rust programlama dili"]),
+            (":open RUST p", &[":open Rust programlama dili"]),
+            (":channel g", &[":channel gündem"]),
+            (":page 1", &[]),
+            ("rust", &[]),
+        ];
+
+        for &(input, expected) in cases {
+            assert_eq!(complete(input, titles), expected, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+/// A simple ini-like configuration file.
+///
+/// ```text
+/// [filter]
+/// author = ssg
+/// keyword = spoiler
+/// ```
+///
+/// Keys may be repeated inside a section, every occurrence is kept in order.
+/// The comments and the lines that aren't changed are written back as they
+/// are read.
+#[derive(Debug, Default)]
+pub struct Config {
+    sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+struct Section {
+    name: String,
+    /// The `[name]` line as it's read, none for the new sections and the
+    /// lines before the first section
+    header: Option<String>,
+    lines: Vec<Line>,
+}
+
+#[derive(Debug)]
+enum Line {
+    /// A `key = value` pair, with the line as it's read if it isn't changed
+    Pair(String, String, Option<String>),
+    /// Comments, blank lines and the lines that can't be parsed
+    Other(String),
+}
+
+impl Section {
+    fn new(name: &str, header: Option<String>) -> Section {
+        Section {
+            name: name.to_string(),
+            header: header,
+            lines: vec![],
+        }
+    }
+
+    fn has_pairs(&self) -> bool {
+        self.lines.iter().any(|x| match *x {
+            Line::Pair(..) => true,
+            Line::Other(_) => false,
+        })
+    }
+}
+
+impl Config {
+    /// Returns the path of the config file, `$XDG_CONFIG_HOME/eksi-cli/config`
+    /// or `~/.config/eksi-cli/config`.
+    pub fn path() -> Option<PathBuf> {
+        let base = match env::var("XDG_CONFIG_HOME") {
+            Ok(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+        };
+
+        Some(base.join("eksi-cli").join("config"))
+    }
+
+    /// Loads the config file, returns an empty config if it doesn't exist.
+    pub fn load() -> Config {
+        let mut text = String::new();
+        let read = Config::path()
+            .and_then(|path| File::open(path).ok())
+            .map(|mut file| file.read_to_string(&mut text));
+
+        match read {
+            Some(Ok(_)) => Config::parse(&text),
+            _ => Config::default(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Config::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "can't find config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        File::create(path)?.write_all(self.to_string().as_bytes())
+    }
+
+    pub fn parse(text: &str) -> Config {
+        let mut sections = vec![Section::new("", None)];
+
+        for raw in text.lines() {
+            let line = raw.trim();
+            let parsed = if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                Line::Other(raw.to_string())
+            } else if line.starts_with('[') && line.ends_with(']') {
+                let name = line[1..line.len() - 1].trim();
+                sections.push(Section::new(name, Some(raw.to_string())));
+                continue;
+            } else if let Some(i) = line.find('=') {
+                Line::Pair(line[..i].trim().to_string(), line[i + 1..].trim().to_string(), Some(raw.to_string()))
+            } else {
+                Line::Other(raw.to_string())
+            };
+
+            sections.last_mut().unwrap().lines.push(parsed);
+        }
+
+        Config { sections: sections }
+    }
+
+
+    /// Returns the first value of `key` in `section`
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.get_all(section, key).into_iter().next()
+    }
+
+    /// Returns every value of `key` in `section`
+    pub fn get_all(&self, section: &str, key: &str) -> Vec<&str> {
+        self.sections.iter()
+            .filter(|x| x.name == section)
+            .flat_map(|x| x.lines.iter())
+            .filter_map(|x| match *x {
+                Line::Pair(ref k, ref v, _) if k == key => Some(v.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the names of the sections starting with `prefix`
+    pub fn sections(&self, prefix: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for section in &self.sections {
+            if section.name.starts_with(prefix) && section.has_pairs() && !names.contains(&section.name.as_str()) {
+                names.push(&section.name);
+            }
+        }
+
+        names
+    }
+
+    /// Replaces every value of `key` in `section` with `value`, the first
+    /// one is replaced in its place
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let mut found = false;
+        for x in self.sections.iter_mut().filter(|x| x.name == section) {
+            let mut i = 0;
+            while i < x.lines.len() {
+                let matched = match x.lines[i] {
+                    Line::Pair(ref k, _, _) => k == key,
+                    Line::Other(_) => false,
+                };
+                if matched && found {
+                    x.lines.remove(i);
+                    continue;
+                }
+                if matched {
+                    x.lines[i] = Line::Pair(key.to_string(), value.to_string(), None);
+                    found = true;
+                }
+                i += 1;
+            }
+        }
+
+        if !found {
+            self.add(section, key, value);
+        }
+    }
+
+    /// Appends a new `key = value` pair to `section`, after its last pair
+    pub fn add(&mut self, section: &str, key: &str, value: &str) {
+        let pair = Line::Pair(key.to_string(), value.to_string(), None);
+        let i = match self.sections.iter().rposition(|x| x.name == section) {
+            Some(i) => i,
+            // The pairs before the first section are in the unnamed one
+            None if section.is_empty() => {
+                self.sections.insert(0, Section::new("", None));
+                0
+            }
+            None => {
+                self.sections.push(Section::new(section, None));
+                self.sections.len() - 1
+            }
+        };
+
+        let lines = &mut self.sections[i].lines;
+        let at = lines.iter()
+            .rposition(|x| match *x {
+                Line::Pair(..) => true,
+                Line::Other(_) => false,
+            })
+            .map_or(0, |x| x + 1);
+        lines.insert(at, pair);
+    }
+
+    /// Removes every `key = value` pair from `section`
+    pub fn remove(&mut self, section: &str, key: &str, value: &str) {
+        self.retain(section, |k, v| !(k == key && v == value));
+    }
+
+    /// Removes every value of `key` from `section`
+    pub fn remove_all(&mut self, section: &str, key: &str) {
+        self.retain(section, |k, _| k != key);
+    }
+
+    /// Keeps the pairs of `section` that `keep` returns true for
+    fn retain<F: Fn(&str, &str) -> bool>(&mut self, section: &str, keep: F) {
+        for x in self.sections.iter_mut().filter(|x| x.name == section) {
+            x.lines.retain(|x| match *x {
+                Line::Pair(ref k, ref v, _) => keep(k, v),
+                Line::Other(_) => true,
+            });
+        }
+    }
+}
+
+impl ToString for Config {
+    fn to_string(&self) -> String {
+        let mut text = String::new();
+
+        for section in &self.sections {
+            match section.header {
+                Some(ref header) => text += &format!("{}\n", header),
+                // The new sections are separated with a blank line
+                None if !section.name.is_empty() && section.has_pairs() => {
+                    if !text.is_empty() && !text.ends_with("\n\n") {
+                        text.push('\n');
+                    }
+                    text += &format!("[{}]\n", section.name);
+                }
+                None if !section.name.is_empty() => continue,
+                None => {}
+            }
+
+            for line in &section.lines {
+                match *line {
+                    Line::Pair(_, _, Some(ref raw)) | Line::Other(ref raw) => text += raw,
+                    Line::Pair(ref key, ref value, None) => text += &format!("{} = {}", key, value),
+                }
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses() {
+        let text = "
+# comment
+; another comment
+top = level
+
+[filter]
+author = ssg
+  keyword  =  spoiler  
+author = ekşi sözlük
+title = a=b
+
+[ ui ]
+titles_width = 40
+";
+        let config = Config::parse(text);
+        let cases: &[(&str, &str, &[&str])] = &[
+            ("", "top", &["level"]),
+            ("filter", "author", &["ssg", "ekşi sözlük"]),
+            ("filter", "keyword", &["spoiler"]),
+            // Only the first `=` splits the line
+            ("filter", "title", &["a=b"]),
+            ("ui", "titles_width", &["40"]),
+            ("ui", "author", &[]),
+            ("theme", "author", &[]),
+        ];
+
+        for &(section, key, values) in cases {
+            assert_eq!(config.get_all(section, key), values, "[{}] {}", section, key);
+        }
+        assert_eq!(config.get("filter", "author"), Some("ssg"));
+    }
+
+    #[test]
+    fn skips_bad_lines() {
+        let cases = &[
+            "no equals sign",
+            "[unclosed",
+            "# key = value",
+            "; key = value",
+            "",
+        ];
+
+        for &line in cases {
+            let text = format!("[filter]\n{}\n", line);
+            let config = Config::parse(&text);
+            assert!(config.sections("").is_empty(), "{:?}", line);
+            // They are written back as they are
+            assert_eq!(config.to_string(), text, "{:?}", line);
+        }
+
+        // A line without a key is kept with an empty key
+        let config = Config::parse("= value");
+        assert_eq!(config.get("", ""), Some("value"));
+    }
+
+    #[test]
+    fn writes() {
+        let mut config = Config::parse("[filter]\nauthor = a\nauthor = b\nkeyword = k\n");
+        config.remove("filter", "author", "a");
+        config.set("ui", "theme", "light");
+        config.set("ui", "theme", "dark");
+        config.add("filter", "keyword", "l");
+
+        assert_eq!(config.to_string(), "[filter]\nauthor = b\nkeyword = k\nkeyword = l\n\n[ui]\ntheme = dark\n");
+        assert_eq!(Config::parse(&config.to_string()).to_string(), config.to_string());
+        assert_eq!(config.sections("fil"), vec!["filter"]);
+    }
+
+    #[test]
+    fn keeps_lines() {
+        let text = "\
+# eksi-cli
+top=1
+
+[filter]
+; engellenenler
+author=a
+author = b
+  keyword =  k
+
+[ui]
+# genislik
+titles_width = 40
+";
+        let cases: &[(&str, &str, &str, &str, &str)] = &[
+            // Added pairs go after the last pair of the section
+            ("add", "filter", "author", "c", "# eksi-cli\ntop=1\n\n[filter]\n; engellenenler\nauthor=a\nauthor = b\n  keyword =  k\nauthor = c\n\n[ui]\n# genislik\ntitles_width = 40\n"),
+            ("remove", "filter", "author", "a", "# eksi-cli\ntop=1\n\n[filter]\n; engellenenler\nauthor = b\n  keyword =  k\n\n[ui]\n# genislik\ntitles_width = 40\n"),
+            // The first value is replaced in its place
+            ("set", "filter", "author", "x", "# eksi-cli\ntop=1\n\n[filter]\n; engellenenler\nauthor = x\n  keyword =  k\n\n[ui]\n# genislik\ntitles_width = 40\n"),
+            ("set", "ui", "titles_width", "30", "# eksi-cli\ntop=1\n\n[filter]\n; engellenenler\nauthor=a\nauthor = b\n  keyword =  k\n\n[ui]\n# genislik\ntitles_width = 30\n"),
+            ("set", "", "top", "2", "# eksi-cli\ntop = 2\n\n[filter]\n; engellenenler\nauthor=a\nauthor = b\n  keyword =  k\n\n[ui]\n# genislik\ntitles_width = 40\n"),
+            ("set", "theme", "dark", "fg=red", "# eksi-cli\ntop=1\n\n[filter]\n; engellenenler\nauthor=a\nauthor = b\n  keyword =  k\n\n[ui]\n# genislik\ntitles_width = 40\n\n[theme]\ndark = fg=red\n"),
+        ];
+
+        assert_eq!(Config::parse(text).to_string(), text);
+        for &(change, section, key, value, expected) in cases {
+            let mut config = Config::parse(text);
+            match change {
+                "add" => config.add(section, key, value),
+                "remove" => config.remove(section, key, value),
+                _ => config.set(section, key, value),
+            }
+            assert_eq!(config.to_string(), expected, "{} [{}] {} = {}", change, section, key, value);
+        }
+
+        // A new unnamed pair goes before the first section
+        let mut config = Config::parse("[ui]\ntheme = dark\n");
+        config.set("", "top", "1");
+        assert_eq!(config.to_string(), "top = 1\n[ui]\ntheme = dark\n");
+    }
+}
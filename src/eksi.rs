@@ -1,4 +1,7 @@
+use std::iter;
+use std::vec;
 use reqwest;
+use serde_json::{self, Value};
 use select::node::Node;
 use select::document::Document;
 use select::predicate::{Attr, Class, Name};
@@ -6,21 +9,15 @@ use select::predicate::{Attr, Class, Name};
 
 // self
 use eksi;
-use endpoints::entry::Entry;
+use endpoints::entry::{self, Entry};
 use endpoints::title::Title;
 use endpoints::author::Author;
+use link::Link;
+use slug;
+use endpoints::channel::Channel;
 
-// FIXME: extract eksi url as constant
 pub fn popular_titles(page: usize) -> Vec<Title> {
-    let text = reqwest::get(&format!("https://eksisozluk.com/basliklar/gundem?p={}", page + 1))
-            .unwrap()
-            .text()
-            .unwrap();
-
-    let doc = Document::from(&text[..]);
-
-                    // skip sol-frame
-    eksi::titles_of(&doc.find(Attr("id", "content")).next().unwrap())
+    Channel::Gundem.titles(page)
 }
 
 // FIXME: get Document, not Node
@@ -28,18 +25,23 @@ pub fn popular_titles(page: usize) -> Vec<Title> {
 pub fn titles_of(doc: &Node) -> Vec<Title> {
     let mut vec = Vec::new();
 
-    let titles = doc.find(Class("topic-list"))
-                    .next()
-                    .unwrap()
-                    .find(Name("li"));
+    let titles = match doc.find(Class("topic-list")).next() {
+        Some(list) => list.find(Name("li")),
+        None => return vec, // Nothing found
+    };
 
     for node in titles {
+        let a = match node.find(Name("a")).next() {
+            Some(a) => a,
+            None    => continue // Skip if we dont have the title.
+        };
+
         vec.push(Title {
-            title: match node.find(Name("a")).next() {
-                Some(a)  => a.children().next().unwrap().text().trim().to_string(),
-                None     => continue // Skip if we dont have the title.
+            title: a.children().next().unwrap().text().trim().to_string(),
+            id: match a.attr("href").and_then(eksi::id_of) {
+                Some(id) => id,
+                None     => continue // Skip the links that are not titles (like entries)
             },
-            id: node.find(Name("a")).next().map(|x| eksi::id_of(x.attr("href").unwrap()).unwrap()).unwrap(),
             popular_count:  node.find(Name("small")).next().map(|x| x.text()),
         });
     }
@@ -47,17 +49,116 @@ pub fn titles_of(doc: &Node) -> Vec<Title> {
     vec
 }
 
+/// Returns the ids of the entries linked from the `.topic-list` in given
+/// `Node`, like the ones in debe
+pub fn entry_ids_of(doc: &Node) -> Vec<i32> {
+    let list = match doc.find(Class("topic-list")).next() {
+        Some(list) => list,
+        None => return vec![],
+    };
+
+    list.find(Name("a"))
+        .filter_map(|a| {
+            let href = a.attr("href")?;
+            if !href.starts_with("/entry/") {
+                return None;
+            }
+
+            href["/entry/".len()..]
+                .chars()
+                .take_while(|x| x.is_numeric())
+                .collect::<String>()
+                .parse()
+                .ok()
+        })
+        .collect()
+}
+
+/// Returns the ids of yesterday's most liked entries (debe), from the list
+/// page alone
+pub fn debe_ids() -> Vec<i32> {
+    let text = match reqwest::get("https://eksisozluk.com/debe").and_then(|mut x| x.text()) {
+        Ok(text) => text,
+        Err(_) => return vec![],
+    };
+    let doc = Document::from(&text[..]);
+
+    match doc.find(Attr("id", "content")).next() {
+        Some(content) => eksi::entry_ids_of(&content),
+        None => vec![],
+    }
+}
+
+/// Yesterday's most liked entries with their titles, see `eksi::debe`
+pub type Debe = iter::FilterMap<vec::IntoIter<i32>, fn(i32) -> Option<(Title, Entry)>>;
+
+/// Returns yesterday's most liked entries (debe) with their titles. The list
+/// is read first, each entry is fetched when the iterator reaches it and
+/// skipped if it can't be.
+///
+/// ```no_run
+/// for (title, entry) in eksicli::eksi::debe().take(3) {
+///     println!("{}: {}", title.title, entry.text);
+/// }
+/// ```
+pub fn debe() -> Debe {
+    debe_ids().into_iter().filter_map(eksi::entry as fn(i32) -> Option<(Title, Entry)>)
+}
+
+/// Returns the entry with given id and its title
+pub fn entry(id: i32) -> Option<(Title, Entry)> {
+    let (title, entries) = eksi::open(&format!("https://eksisozluk.com/entry/{}", id))?;
+    let entry = entries.into_iter().next()?;
+
+    Some((title, entry))
+}
+
 // TODO: Ask for sanitizer mode
 /// Returns the entry list from given `Document`
-pub fn entries_of(doc: &Document, popular: bool) -> Vec<Entry> {
+pub fn entries_of(doc: &Document) -> Vec<Entry> {
+    let title = doc.find(Attr("id", "title"))
+                   .next()
+                   .and_then(|x| eksi::title_of(&x));
+
+    match doc.find(Attr("id", "entry-item-list")).next() {
+        Some(list) => eksi::entries_in(&list, title.as_ref()),
+        None => vec![], // Nothing matched the filter
+    }
+}
+
+/// Returns the count of the pages from the `.pager` in given `Document`, the
+/// pages without a pager have one
+pub fn page_count_of(doc: &Document) -> usize {
+    doc.find(Class("pager"))
+        .next()
+        .and_then(|x| x.attr("data-pagecount"))
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(1)
+}
+
+/// Returns the `Title` from given `#title` node
+pub fn title_of(node: &Node) -> Option<Title> {
+    Some(Title {
+        id: node.attr("data-id")?.parse().ok()?,
+        title: node.attr("data-title")?.to_string(),
+        popular_count: None
+    })
+}
+
+/// Returns the entries inside given `#entry-item-list` node, each one is
+/// tagged with `title`
+pub fn entries_in(list: &Node, title: Option<&Title>) -> Vec<Entry> {
     let mut vec = Vec::new();
 
-    let entries = doc.find(Attr("id", "entry-item-list"))
-                     .next()
-                     .unwrap()
-                     .find(Name("li"));
+    let entries = list.find(Name("li"));
 
     for node in entries {
+        let content = match node.find(Class("content")).next() {
+            Some(content) => content,
+            None => continue,
+        };
+
+        let parsed = Entry::parse(&content);
         vec.push(Entry {
             id: match node.attr("data-id") {
                 Some(a) => a.parse::<i32>().unwrap(),
@@ -70,7 +171,13 @@ pub fn entries_of(doc: &Document, popular: bool) -> Vec<Entry> {
             fav_count: node.attr("data-favorite-count").unwrap().parse::<i32>().unwrap().to_string(),
             is_fav: node.attr("data-isfavorite").unwrap().parse::<bool>().unwrap(),
             date: node.find(Class("entry-date")).next().unwrap().text(),
-            text: Entry::sanitized(&node.find(Class("content")).next().unwrap()),
+            text: parsed.text,
+            links: parsed.links,
+            spoilers: parsed.spoilers,
+            footnotes: parsed.footnotes,
+            refs: parsed.refs,
+            title: title.cloned(),
+            number: None,
         });
     }
 
@@ -78,38 +185,66 @@ pub fn entries_of(doc: &Document, popular: bool) -> Vec<Entry> {
 }
 
 pub fn search(query: &str) -> Option<(Title, Vec<Entry>)> {
-    let text = reqwest::get(&format!("https://eksisozluk.com/?q={}", query))
-            .unwrap()
-            .text()
-            .unwrap();
+    let (title, mut entries) = eksi::open(&format!("https://eksisozluk.com/?q={}", slug::encode(query)))?;
+    entry::set_numbers(&mut entries, 0);
+
+    Some((title, entries))
+}
+
+/// Returns the title and its entries from given title url
+pub fn open(url: &str) -> Option<(Title, Vec<Entry>)> {
+    let text = reqwest::get(url).ok()?.text().ok()?;
 
     let doc = Document::from(&text[..]);
-    let title_not_found = doc.find(Attr("id", "topic")).next().unwrap().attr("data-not-found");
+    let title_not_found = doc.find(Attr("id", "topic")).next()?.attr("data-not-found");
     match title_not_found {
         Some("true") => { // The title doesn't exists
             None
         },
         _ => { // The title exists
-            let title_node = doc.find(Attr("id", "title")).next().unwrap();
-            let title = Title {
-                id: title_node.attr("data-id").unwrap().parse().unwrap(),
-                title: title_node.attr("data-title").unwrap().to_string(),
-                popular_count: None
-            };
-            let entries = entries_of(&doc, false);
+            let title = eksi::title_of(&doc.find(Attr("id", "title")).next()?)?;
+            let entries = entries_of(&doc);
 
             Some((title, entries))
         }
     }
 }
 
+/// Title and nick suggestions for a partially typed query
+#[derive(Debug, Default, Clone)]
+pub struct Suggestions {
+    pub titles: Vec<String>,
+    pub nicks: Vec<String>,
+}
+
+/// Returns the autocomplete suggestions of the site for given query. Queries
+/// starting with `@` only suggest nicks.
+pub fn autocomplete(query: &str) -> Suggestions {
+    let text = reqwest::get(&format!("https://eksisozluk.com/autocomplete/query?q={}", slug::encode(query)))
+            .and_then(|mut x| x.text())
+            .unwrap_or_default();
+
+    let json: Value = match serde_json::from_str(&text) {
+        Ok(json) => json,
+        Err(_) => return Suggestions::default(),
+    };
+
+    let strings = |key: &str| -> Vec<String> {
+        json[key].as_array()
+            .map(|x| x.iter().filter_map(|x| x.as_str()).map(|x| x.to_string()).collect())
+            .unwrap_or_default()
+    };
+
+    Suggestions {
+        titles: strings("Titles"),
+        nicks: strings("Nicks"),
+    }
+}
+
 /// Returns the id of particular tite from given url
 pub fn id_of(href: &str) -> Option<i32> {
-    href.split("--")
-        .collect::<Vec<_>>()[1]
-        .chars()
-        .take_while(|x| x.is_numeric())
-        .collect::<String>()
-        .parse()
-        .ok()
+    match Link::parse(href)? {
+        Link::Title { id, .. } => Some(id),
+        _ => None,
+    }
 }
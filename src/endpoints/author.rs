@@ -1,5 +1,38 @@
-#[derive(Debug)]
+use reqwest;
+use select::document::Document;
+use select::predicate::{Attr, Class};
+
+use eksi;
+use slug;
+use endpoints::entry::Entry;
+
+#[derive(Debug, Clone)]
 pub struct Author {
     pub id: i32,
     pub name: String
 }
+
+impl Author {
+    /// Returns the last entries of the author with given nick, each entry is
+    /// tagged with its title
+    pub fn entries_of(nick: &str, page: usize) -> Vec<Entry> {
+        let text = reqwest::get(&format!("https://eksisozluk.com/son-entryleri?nick={}&p={}", slug::encode(nick), page + 1))
+                    .unwrap()
+                    .text()
+                    .unwrap();
+        let doc = Document::from(&text[..]);
+
+        let mut vec = Vec::new();
+        for topic in doc.find(Class("topic-item")) {
+            let title = topic.find(Attr("id", "title"))
+                             .next()
+                             .and_then(|x| eksi::title_of(&x));
+
+            if let Some(list) = topic.find(Attr("id", "entry-item-list")).next() {
+                vec.append(&mut eksi::entries_in(&list, title.as_ref()));
+            }
+        }
+
+        vec
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use reqwest;
+use select::document::Document;
+use select::predicate::Attr;
+
+use eksi;
+use slug;
+use endpoints::title::Title;
+
+/// Topic channels of the site, `/basliklar/kanal/<name>`
+pub const TOPICS: &[&str] = &[
+    "spor", "siyaset", "anket", "iliskiler", "ekonomi", "bilim", "tarih",
+    "sinema", "muzik", "oyun", "teknoloji", "edebiyat", "saglik", "moda",
+    "yeme-icme", "seyahat", "tv", "egitim", "spoiler", "haber",
+];
+
+/// Title lists of the left frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum Channel {
+    Gundem,
+    Bugun,
+    /// Yesterday's most liked entries (dunun en begenilen entry'leri)
+    Debe,
+    Son,
+    /// Titles from this day of the given year, the current year if `None`
+    TarihteBugun(Option<u32>),
+    Kenar,
+    Caylaklar,
+    /// `#spor`, `#siyaset`...
+    Kanal(String),
+}
+
+impl Channel {
+    /// Returns every channel in the order they are shown in the switcher
+    pub fn all() -> Vec<Channel> {
+        let mut channels = vec![
+            Channel::Gundem,
+            Channel::Bugun,
+            Channel::Debe,
+            Channel::Son,
+            Channel::TarihteBugun(None),
+            Channel::Kenar,
+            Channel::Caylaklar,
+        ];
+        channels.extend(TOPICS.iter().map(|x| Channel::Kanal(x.to_string())));
+
+        channels
+    }
+
+    /// Parses the names shown by `Display`, like `gundem` or `#spor`
+    pub fn from_name(name: &str) -> Option<Channel> {
+        let name = name.trim().to_lowercase();
+        if name.starts_with('#') {
+            return Some(Channel::Kanal(name[1..].to_string()));
+        }
+
+        match name.as_str() {
+            "gundem" | "gündem" => Some(Channel::Gundem),
+            "bugun" | "bugün" => Some(Channel::Bugun),
+            "debe" => Some(Channel::Debe),
+            "son" => Some(Channel::Son),
+            "tarihte-bugun" | "tarihte bugün" => Some(Channel::TarihteBugun(None)),
+            "kenar" => Some(Channel::Kenar),
+            "caylaklar" | "çaylaklar" => Some(Channel::Caylaklar),
+            // tarihte-bugun/2010
+            _ if name.starts_with("tarihte-bugun/") => {
+                let year = name["tarihte-bugun/".len()..].parse().ok()?;
+                Some(Channel::TarihteBugun(Some(year)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Channels without pagination only have the first page
+    pub fn has_pages(&self) -> bool {
+        match *self {
+            Channel::Debe => false,
+            _ => true,
+        }
+    }
+
+    pub fn to_url(&self, page: usize) -> String {
+        let path = match *self {
+            Channel::Gundem => "basliklar/gundem".to_string(),
+            Channel::Bugun => "basliklar/bugun".to_string(),
+            Channel::Debe => return "https://eksisozluk.com/debe".to_string(),
+            Channel::Son => "basliklar/son".to_string(),
+            Channel::TarihteBugun(Some(year)) => format!("basliklar/tarihte-bugun?year={}&", year),
+            Channel::TarihteBugun(None) => "basliklar/tarihte-bugun".to_string(),
+            Channel::Kenar => "basliklar/kenar".to_string(),
+            Channel::Caylaklar => "basliklar/caylaklar".to_string(),
+            Channel::Kanal(ref name) => format!("basliklar/kanal/{}", slug::encode(name)),
+        };
+
+        let separator = if path.ends_with('&') { "" } else { "?" };
+        format!("https://eksisozluk.com/{}{}p={}", path, separator, page + 1)
+    }
+
+    /// Returns the given page of the titles in the channel. Debe is a list
+    /// of entries, not titles, it's read with `eksi::debe`.
+    pub fn titles(&self, page: usize) -> Vec<Title> {
+        if (page > 0 && !self.has_pages()) || *self == Channel::Debe {
+            return vec![];
+        }
+
+        let text = match reqwest::get(&self.to_url(page)).and_then(|mut x| x.text()) {
+            Ok(text) => text,
+            Err(_) => return vec![],
+        };
+        let doc = Document::from(&text[..]);
+
+                        // skip sol-frame
+        let content = match doc.find(Attr("id", "content")).next() {
+            Some(content) => content,
+            None => return vec![],
+        };
+
+        eksi::titles_of(&content)
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Channel::Gundem => write!(f, "gündem"),
+            Channel::Bugun => write!(f, "bugün"),
+            Channel::Debe => write!(f, "debe"),
+            Channel::Son => write!(f, "son"),
+            Channel::TarihteBugun(Some(year)) => write!(f, "tarihte bugün ({})", year),
+            Channel::TarihteBugun(None) => write!(f, "tarihte bugün"),
+            Channel::Kenar => write!(f, "kenar"),
+            Channel::Caylaklar => write!(f, "çaylaklar"),
+            Channel::Kanal(ref name) => write!(f, "#{}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names() {
+        let cases = vec![
+            ("gundem", Some(Channel::Gundem)),
+            (" Gündem ", Some(Channel::Gundem)),
+            ("bugün", Some(Channel::Bugun)),
+            ("debe", Some(Channel::Debe)),
+            ("son", Some(Channel::Son)),
+            ("tarihte bugün", Some(Channel::TarihteBugun(None))),
+            ("tarihte-bugun/2010", Some(Channel::TarihteBugun(Some(2010)))),
+            ("tarihte-bugun/", None),
+            ("tarihte-bugun/dun", None),
+            ("kenar", Some(Channel::Kenar)),
+            ("çaylaklar", Some(Channel::Caylaklar)),
+            ("#spor", Some(Channel::Kanal("spor".to_string()))),
+            ("#Sinema", Some(Channel::Kanal("sinema".to_string()))),
+            // A year alone isn't a channel
+            ("2010", None),
+            ("5", None),
+            ("spor", None),
+            ("", None),
+        ];
+
+        for (name, channel) in cases {
+            assert_eq!(Channel::from_name(name), channel, "{:?}", name);
+        }
+
+        for channel in Channel::all() {
+            assert_eq!(Channel::from_name(&channel.to_string()), Some(channel.clone()), "{}", channel);
+        }
+    }
+}
@@ -1,12 +1,97 @@
 use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
 use select::node::Node;
 use select::predicate::{Class, Name};
 
 // self
 use endpoints::author::Author;
+use endpoints::title::Title;
+
+/// Count of the entries in a page of a title
+pub const PAGE_SIZE: usize = 10;
+
+/// What to do with the spoiler blocks of an entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spoilers {
+    /// Leave the text as it is
+    Keep,
+    /// Remove the blocks with their contents
+    Strip,
+    /// Keep the contents between `[spoiler]` and `[/spoiler]`
+    Mark,
+    /// Replace the contents with a single line
+    Collapse,
+}
+
+impl FromStr for Spoilers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Spoilers, String> {
+        match s {
+            "keep" => Ok(Spoilers::Keep),
+            "strip" => Ok(Spoilers::Strip),
+            "mark" => Ok(Spoilers::Mark),
+            "collapse" => Ok(Spoilers::Collapse),
+            _ => Err(format!("Unknown spoiler mode: {} (keep, strip, mark, collapse)", s)),
+        }
+    }
+}
+
+/// Returns true if given line is a `--- spoiler ---` marker
+fn is_spoiler_marker(line: &str) -> bool {
+    let line: String = line.chars().filter(|x| !x.is_whitespace()).collect();
+    line.to_lowercase() == "---spoiler---"
+}
+
+/// Returns the byte ranges of the spoiler blocks in given text, markers
+/// included. An unclosed block lasts until the end of the text.
+pub fn spoilers_of(text: &str) -> Vec<(usize, usize)> {
+    let mut spoilers = vec![];
+    let mut start = None;
+    let mut offset = 0;
+
+    for line in text.split('\n') {
+        let end = offset + line.len();
+        if is_spoiler_marker(line) {
+            match start.take() {
+                Some(start) => spoilers.push((start, end)),
+                None => start = Some(offset),
+            }
+        }
+        offset = end + 1;
+    }
+
+    if let Some(start) = start {
+        spoilers.push((start, text.len()));
+    }
+
+    spoilers
+}
+
+/// What a reference in an entry points to
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefKind {
+    /// `(bkz: ...)`, hede and gizli bkz, with the query
+    Bkz(String),
+    /// `@nick`
+    Mention(String),
+    /// An url, `links[n]`
+    Link(usize),
+}
+
+/// A reference in the text of an entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// The reference as it's shown in the text
+    pub text: String,
+    pub kind: RefKind,
+    /// Byte range of the reference in the text of the entry
+    pub range: Range<usize>,
+}
 
 // TODO: add last_edit (parse from date)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Entry {
     pub id: i32,
     pub author: Author,
@@ -14,48 +99,507 @@ pub struct Entry {
     pub is_fav: bool,
     pub text: String,
     pub date: String,
+    /// The title that the entry belongs to, if known
+    pub title: Option<Title>,
+    /// Ordinal of the entry in its title, if known
+    pub number: Option<usize>,
+    /// Urls in the text, `[n]` in the text is `links[n]`
+    pub links: Vec<String>,
+    /// Byte ranges of the `--- spoiler ---` blocks in the text
+    pub spoilers: Vec<(usize, usize)>,
+    /// Byte offset of the `[n]: url` footnotes of the links in the text, its
+    /// length if there are none
+    pub footnotes: usize,
+    /// Bkz, mentions and links in the text, in order
+    pub refs: Vec<Reference>,
+}
+
+/// Text and links of an entry, built by walking its DOM
+#[derive(Default)]
+struct Content {
+    text: String,
+    /// Urls and whether their text is the url itself
+    links: Vec<(String, bool)>,
+    refs: Vec<Reference>,
+}
+
+impl Content {
+    fn walk(&mut self, node: &Node) {
+        for child in node.children() {
+            if let Some(text) = child.as_text() {
+                // Entities are already decoded by the parser
+                self.text += &text.replace("\r", "").replace('\u{a0}', " ");
+                continue;
+            }
+
+            match child.name() {
+                Some("br") => self.text.push('\n'),
+                Some("script") | Some("style") => {}
+                // gizli bkz, `<sup class="ab"><a data-query="...">*</a></sup>`
+                Some(_) if child.is(Class("ab")) => {
+                    match child.find(Name("a")).next().and_then(|x| x.attr("data-query")) {
+                        Some(query) => self.push_ref(query.trim(), RefKind::Bkz(query.trim().to_string())),
+                        None => self.walk(&child),
+                    }
+                }
+                Some("a") if child.is(Class("url")) => {
+                    let url = child.attr("href").unwrap_or("it goes nowhere").trim().to_string();
+                    let text = child.text().trim().to_string();
+                    let inline = text == url;
+
+                    let n = self.links.len();
+                    self.push_ref(&format!("{}[{}]", text, n), RefKind::Link(n));
+                    self.links.push((url, inline));
+                }
+                // bkz, hede and @mentions, `<a class="b" data-query="...">`
+                Some("a") if child.is(Class("b")) => {
+                    let text = child.text();
+                    let query = child.attr("data-query").unwrap_or(&text).trim().to_string();
+                    let kind = if query.starts_with('@') && query.len() > 1 {
+                        RefKind::Mention(query[1..].to_string())
+                    } else {
+                        RefKind::Bkz(query)
+                    };
+
+                    self.push_ref(text.trim(), kind);
+                }
+                // The other inline elements are replaced with their texts
+                _ => self.walk(&child),
+            }
+        }
+    }
+
+    /// Appends a reference to the text
+    fn push_ref(&mut self, text: &str, kind: RefKind) {
+        let start = self.text.len();
+        self.text += text;
+        if !text.is_empty() {
+            self.refs.push(Reference {
+                text: text.to_string(),
+                kind: kind,
+                range: start..self.text.len(),
+            });
+        }
+    }
+}
+
+/// Text of an entry with its links, spoilers and references, see
+/// `Entry::parse`
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub text: String,
+    pub links: Vec<String>,
+    pub spoilers: Vec<(usize, usize)>,
+    pub footnotes: usize,
+    pub refs: Vec<Reference>,
+}
+
+/// Sets the ordinals of consecutive entries of a title, `offset` is the
+/// count of the entries before them
+pub fn set_numbers(entries: &mut [Entry], offset: usize) {
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.number = Some(offset + i + 1);
+    }
+}
+
+/// Text of an entry as it's shown, with the positions of its parts in it
+#[derive(Debug, PartialEq)]
+pub struct Shown {
+    pub text: String,
+    /// Byte ranges of the spoiler blocks, or of what they are replaced with
+    pub spoilers: Vec<Range<usize>>,
+    /// Byte offset of the footnotes of the links
+    pub footnotes: usize,
+    /// Byte ranges of the shown references, with their indexes in `refs`.
+    /// The ones in collapsed spoilers aren't shown.
+    pub refs: Vec<(Range<usize>, usize)>,
+}
+
+/// Moves the positions in the text of an entry to the text that's shown.
+/// Only the positions in the copied parts have a place in the shown text.
+struct Moves {
+    /// Ranges of the copied parts and where they start in the shown text
+    copied: Vec<(Range<usize>, usize)>,
+    /// Bytes trimmed from the start of the shown text
+    trimmed: usize,
+    /// Length of the shown text
+    len: usize,
+}
+
+impl Moves {
+    /// Moves a position in the shown text before it's trimmed
+    fn shift(&self, position: usize) -> usize {
+        position.saturating_sub(self.trimmed).min(self.len)
+    }
+
+    /// Moves a position, the ones between the copied parts go to the end
+    /// of the part before them. The parts are in order.
+    fn position(&self, position: usize) -> usize {
+        match self.copied.iter().rev().find(|x| x.0.start <= position) {
+            Some(&(ref from, to)) => self.shift(to + position.min(from.end) - from.start),
+            None => 0,
+        }
+    }
+
+    /// Moves a range that's in a single copied part
+    fn range(&self, range: &Range<usize>) -> Option<Range<usize>> {
+        let &(ref from, to) = self.copied.iter()
+            .find(|x| x.0.start <= range.start && range.end <= x.0.end)?;
+
+        Some(self.shift(to + range.start - from.start)..self.shift(to + range.end - from.start))
+    }
 }
 
 impl Entry {
-    // TODO: handle sanitizer mode
-    // TODO: find a way to do it internally
-    pub fn sanitized(node: &Node) -> String {
-        let mut text = node.inner_html();
+    /// Returns the text with the spoiler blocks handled by given mode
+    pub fn text_with(&self, mode: Spoilers) -> String {
+        self.shown(mode).text
+    }
+
+    /// Returns the text with the spoiler blocks handled by given mode, with
+    /// the positions of the spoilers and the footnotes in it
+    pub fn shown(&self, mode: Spoilers) -> Shown {
+        let mut text = String::with_capacity(self.text.len());
+        let mut spoilers = vec![];
+        let mut copied = vec![];
+        let mut last = 0;
+        for &(start, end) in &self.spoilers {
+            copied.push((last..start, text.len()));
+            text += &self.text[last..start];
+
+            let block = &self.text[start..end];
+            let shown = text.len();
+            match mode {
+                Spoilers::Keep => {
+                    copied.push((start..end, text.len()));
+                    text += block;
+                }
+                Spoilers::Strip => {}
+                Spoilers::Mark => {
+                    let mut lines = vec![];
+                    let mut offset = start;
+                    for line in block.split('\n') {
+                        if !is_spoiler_marker(line) {
+                            lines.push(offset..offset + line.len());
+                        }
+                        offset += line.len() + 1;
+                    }
+                    // The lines are already trimmed from the right, only
+                    // the blank lines around the contents are dropped
+                    let blank = |x: &Range<usize>| self.text[x.clone()].trim().is_empty();
+                    let first = lines.iter().position(|x| !blank(x)).unwrap_or(lines.len());
+                    let last = lines.iter().rposition(|x| !blank(x)).map_or(first, |x| x + 1);
 
-        // bkz
-        for x in node.find(Class("b")) {
-            text = text.replace(x.html().trim(), x.text().trim());
+                    text += "[spoiler]\n";
+                    for (i, line) in lines[first..last].iter().enumerate() {
+                        let mut line = line.clone();
+                        if i == 0 {
+                            line.start = line.end - self.text[line.clone()].trim_left().len();
+                        } else {
+                            text.push('\n');
+                        }
+                        copied.push((line.clone(), text.len()));
+                        text += &self.text[line];
+                    }
+                    text += "\n[/spoiler]";
+                }
+                Spoilers::Collapse => {
+                    let lines = block.lines().filter(|x| !is_spoiler_marker(x)).count();
+                    text += &format!("--- spoiler ({} satir gizli) ---", lines);
+                }
+            }
+            spoilers.push(shown..text.len());
+            last = end;
         }
+        copied.push((last..self.text.len(), text.len()));
+        text += &self.text[last..];
 
-        // gizli bkz
-        for x in node.find(Class("ab")) {
-            let gbkz = x.find(Name("a")).next().unwrap().attr("data-query").unwrap().trim();
-            text = text.replace(x.html().trim(), gbkz);
+        // The blocks stripped from the ends leave spaces behind
+        let moves = Moves {
+            copied: copied,
+            trimmed: text.len() - text.trim_left().len(),
+            len: text.trim().len(),
+        };
+        let footnotes = self.footnotes..self.footnotes;
+
+        Shown {
+            spoilers: spoilers.into_iter()
+                .map(|x| moves.shift(x.start)..moves.shift(x.end))
+                .filter(|x| x.start < x.end)
+                .collect(),
+            footnotes: moves.range(&footnotes).map_or(moves.len, |x| x.start),
+            refs: self.refs.iter()
+                .enumerate()
+                .filter_map(|(i, x)| moves.range(&x.range).map(|range| (range, i)))
+                .collect(),
+            text: text.trim().to_string(),
         }
+    }
 
-        // links
-        // TODO: <a>http://linkin-kendisi</a>
-        let mut links = vec![];
-        for (i, x) in node.find(Class("url")).enumerate() {
-            let url_text = x.text().trim().to_string()
-                + "[" + &i.to_string() + "]";
-            text = text.replace(x.html().trim(), &url_text);
-            links.push(x.attr("href").unwrap_or("it goes nowhere"));
+    /// Returns the day that the entry was written as `YYYY-MM-DD`, from its
+    /// date like `01.06.2015 14:23 ~ 15:00`
+    pub fn day(&self) -> Option<String> {
+        let date = self.date.trim();
+        let parts: Vec<&str> = date.get(..10)?.split('.').collect();
+        let valid = parts.len() == 3
+            && parts.iter().zip(&[2, 2, 4]).all(|(x, &len)| x.len() == len && x.bytes().all(|b| b.is_ascii_digit()));
+        if !valid {
+            return None;
         }
 
-        let links_text: String = links.into_iter()
-            .enumerate()
-            .map(|(i, x)| format!("\n[{}]: {}", i, x))
+        Some(format!("{}-{}-{}", parts[2], parts[1], parts[0]))
+    }
+
+    /// Returns the url of the entry
+    pub fn url(&self) -> String {
+        format!("https://eksisozluk.com/entry/{}", self.id)
+    }
+
+    /// Returns the plain text of given `.content` node. Links are followed by
+    /// their `[n]` numbers and listed at the end, unless their text is the
+    /// url itself.
+    pub fn sanitized(node: &Node) -> String {
+        Entry::parse(node).text
+    }
+
+    /// Returns the text of given `.content` node like `sanitized`, with its
+    /// links, spoiler blocks and references
+    pub fn parse(node: &Node) -> Parsed {
+        let mut content = Content::default();
+        content.walk(node);
+
+        // The lines are trimmed, the references move with them
+        let mut body = String::with_capacity(content.text.len());
+        let mut copied = vec![];
+        let mut offset = 0;
+        for (i, line) in content.text.split('\n').enumerate() {
+            if i > 0 {
+                body.push('\n');
+            }
+            let kept = line.trim_right().len();
+            copied.push((offset..offset + kept, body.len()));
+            body += &line[..kept];
+            offset += line.len() + 1;
+        }
+        let moves = Moves {
+            copied: copied,
+            trimmed: body.len() - body.trim_left().len(),
+            len: body.trim().len(),
+        };
+        let refs = content.refs.into_iter()
+            .map(|x| Reference {
+                range: moves.position(x.range.start)..moves.position(x.range.end),
+                ..x
+            })
             .collect();
 
-        text.replace("<br/>", "\n")
-            .replace("<br />", "\n")
-            .replace("<br>", "\n")
-            .replace("&amp;", "&")
-            .replace("&lt;", "<")
-            .replace("&gt;", ">")
-            .trim()
-            .to_string()
-            + &links_text
+        let mut text = body.trim().to_string();
+        // The footnotes aren't a part of the body, an unclosed spoiler
+        // doesn't hide them
+        let spoilers = spoilers_of(&text);
+        let footnotes = text.len();
+
+        for (i, &(ref url, inline)) in content.links.iter().enumerate() {
+            if !inline {
+                text += &format!("\n[{}]: {}", i, url);
+            }
+        }
+
+        Parsed {
+            text: text,
+            links: content.links.into_iter().map(|(url, _)| url).collect(),
+            spoilers: spoilers,
+            footnotes: footnotes,
+            refs: refs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use select::document::Document;
+
+    fn parse(html: &str) -> Parsed {
+        let doc = Document::from(&format!("<div class=\"content\">{}</div>", html)[..]);
+        let node = doc.find(Class("content")).next().unwrap();
+
+        Entry::parse(&node)
+    }
+
+    fn entry(date: &str, parsed: Parsed) -> Entry {
+        Entry {
+            id: 1,
+            author: Author { id: 1, name: String::new() },
+            fav_count: "0".to_string(),
+            is_fav: false,
+            text: parsed.text,
+            date: date.to_string(),
+            title: None,
+            number: None,
+            links: parsed.links,
+            spoilers: parsed.spoilers,
+            footnotes: parsed.footnotes,
+            refs: parsed.refs,
+        }
+    }
+
+    #[test]
+    fn walks() {
+        let cases = &[
+            ("merhaba<br>dünya", "merhaba\ndünya"),
+            ("  a  <br>b  ", "a\nb"),
+            ("a\r\nb", "a\nb"),
+            ("a &amp; b&nbsp;c &lt;3", "a & b c <3"),
+            ("<i>eğik</i> ve <b>kalın</b>", "eğik ve kalın"),
+            ("a<script>alert(1)</script><style>p {}</style>b", "ab"),
+            ("(bkz: <a class=\"b\" href=\"/?q=rust\" data-query=\"rust\">rust</a>)", "(bkz: rust)"),
+            ("<a class=\"b\" data-query=\"@ssg\">@ssg</a>", "@ssg"),
+            ("gizli<sup class=\"ab\"><a data-query=\"gizli bkz\">*</a></sup>", "gizligizli bkz"),
+            ("<sup class=\"ab\">*</sup>", "*"),
+            ("東京 🎉", "東京 🎉"),
+            ("", ""),
+        ];
+
+        for &(html, text) in cases {
+            let parsed = parse(html);
+            assert_eq!(parsed.text, text, "text of {:?}", html);
+            assert!(parsed.links.is_empty(), "links of {:?}", html);
+        }
+    }
+
+    #[test]
+    fn walks_links() {
+        let cases: &[(&str, &str, &[&str])] = &[
+            (
+                "<a class=\"url\" href=\"https://a.com\">site</a>",
+                "site[0]\n[0]: https://a.com",
+                &["https://a.com"],
+            ),
+            // A link whose text is the url isn't repeated in the footnotes
+            (
+                "<a class=\"url\" href=\"https://a.com\">https://a.com</a> ve <a class=\"url\" href=\"https://b.com\">b</a>",
+                "https://a.com[0] ve b[1]\n[1]: https://b.com",
+                &["https://a.com", "https://b.com"],
+            ),
+            (
+                "<a class=\"url\">bos</a>",
+                "bos[0]\n[0]: it goes nowhere",
+                &["it goes nowhere"],
+            ),
+        ];
+
+        for &(html, text, links) in cases {
+            let parsed = parse(html);
+            assert_eq!(parsed.text, text, "text of {:?}", html);
+            assert_eq!(parsed.links, links, "links of {:?}", html);
+        }
+    }
+
+    #[test]
+    fn finds_spoilers() {
+        let cases: &[(&str, &[(usize, usize)])] = &[
+            ("--- spoiler ---<br>gizli<br>--- spoiler ---", &[(0, 37)]),
+            ("a<br>--- <b>spoiler</b> ---<br>b<br>---spoiler---<br>c", &[(2, 33)]),
+            ("a<br>spoiler: --- spoiler ---", &[]),
+            // An unclosed block doesn't reach the footnotes
+            ("--- spoiler ---<br><a class=\"url\" href=\"https://a.com\">x</a>", &[(0, 20)]),
+        ];
+
+        for &(html, spoilers) in cases {
+            assert_eq!(parse(html).spoilers, spoilers, "spoilers of {:?}", html);
+        }
+    }
+
+    #[test]
+    fn shows_spoilers() {
+        let middle = "a<br>--- spoiler ---<br>b<br>--- spoiler ---<br>c";
+        let first = "--- spoiler ---<br>x<br>--- spoiler ---<br><a class=\"url\" href=\"https://a.com\">y</a>";
+        let cases: &[(&str, Spoilers, &str, &[(usize, usize)], usize)] = &[
+            (middle, Spoilers::Keep, "a\n--- spoiler ---\nb\n--- spoiler ---\nc", &[(2, 35)], 37),
+            (middle, Spoilers::Collapse, "a\n--- spoiler (1 satir gizli) ---\nc", &[(2, 33)], 35),
+            (middle, Spoilers::Mark, "a\n[spoiler]\nb\n[/spoiler]\nc", &[(2, 24)], 26),
+            (middle, Spoilers::Strip, "a\n\nc", &[], 4),
+            (first, Spoilers::Collapse, "--- spoiler (1 satir gizli) ---\ny[0]\n[0]: https://a.com", &[(0, 31)], 36),
+            // The text is trimmed after the block is stripped
+            (first, Spoilers::Strip, "y[0]\n[0]: https://a.com", &[], 4),
+        ];
+
+        for &(html, mode, text, spoilers, footnotes) in cases {
+            let shown = entry("", parse(html)).shown(mode);
+            let spoilers: Vec<Range<usize>> = spoilers.iter().map(|&(a, b)| a..b).collect();
+            assert_eq!(shown.text, text, "text of {:?} {:?}", mode, html);
+            assert_eq!(shown.spoilers, spoilers, "spoilers of {:?} {:?}", mode, html);
+            assert_eq!(shown.footnotes, footnotes, "footnotes of {:?} {:?}", mode, html);
+        }
+    }
+
+    #[test]
+    fn walks_refs() {
+        let bkz = "(bkz: <a class=\"b\" data-query=\"rust\">rust</a>)";
+        let cases: &[(&str, &[(&str, RefKind)])] = &[
+            (bkz, &[("rust", RefKind::Bkz("rust".to_string()))]),
+            // The ranges move with the trimmed spaces and lines
+            (
+                "<br>  \n  a  <br>  <a class=\"b\" data-query=\"@ssg\">@ssg</a>  <br> b<sup class=\"ab\"><a data-query=\" gizli \">*</a></sup>",
+                &[("@ssg", RefKind::Mention("ssg".to_string())), ("gizli", RefKind::Bkz("gizli".to_string()))],
+            ),
+            (
+                "<a class=\"url\" href=\"https://a.com\">site</a> ve <a class=\"url\" href=\"https://b.com\">https://b.com</a>",
+                &[("site[0]", RefKind::Link(0)), ("https://b.com[1]", RefKind::Link(1))],
+            ),
+            ("<a class=\"b\" data-query=\"bos\"> </a>", &[]),
+        ];
+
+        for &(html, refs) in cases {
+            let parsed = parse(html);
+            let found: Vec<(&str, RefKind)> = parsed.refs.iter()
+                .map(|x| (&parsed.text[x.range.clone()], x.kind.clone()))
+                .collect();
+            assert_eq!(found, refs, "refs of {:?}", html);
+        }
+    }
+
+    #[test]
+    fn shows_refs() {
+        let html = "<a class=\"b\" data-query=\"a\">a</a><br>--- spoiler ---<br><br> x <a class=\"b\" data-query=\"b\">b</a><br>--- spoiler ---<br><a class=\"b\" data-query=\"c\">c</a>";
+        let cases: &[(Spoilers, &[&str])] = &[
+            (Spoilers::Keep, &["a", "b", "c"]),
+            (Spoilers::Mark, &["a", "b", "c"]),
+            (Spoilers::Collapse, &["a", "c"]),
+            (Spoilers::Strip, &["a", "c"]),
+        ];
+
+        for &(mode, refs) in cases {
+            let entry = entry("", parse(html));
+            let shown = entry.shown(mode);
+            let found: Vec<&str> = shown.refs.iter()
+                .map(|&(ref range, i)| {
+                    assert_eq!(&shown.text[range.clone()], entry.refs[i].text, "ref {} of {:?}", i, mode);
+                    &shown.text[range.clone()]
+                })
+                .collect();
+            assert_eq!(found, refs, "refs of {:?}", mode);
+        }
+    }
+
+    #[test]
+    fn days() {
+        let cases = &[
+            ("01.06.2015", Some("2015-06-01")),
+            ("01.06.2015 14:23", Some("2015-06-01")),
+            ("  15.02.1999 23:15 ~ 16.02.1999 10:00", Some("1999-02-15")),
+            ("31.12.2020 ~ 01:00", Some("2020-12-31")),
+            ("1.6.2015", None),
+            ("2015-06-01", None),
+            ("01.06.15", None),
+            ("", None),
+            ("ış.ık.2015", None),
+        ];
+
+        for &(date, day) in cases {
+            let entry = entry(date, Parsed::default());
+            assert_eq!(entry.day(), day.map(|x| x.to_string()), "day of {:?}", date);
+        }
     }
 }
@@ -1,3 +1,5 @@
 pub mod author;
 pub mod title;
 pub mod entry;
+pub mod search;
+pub mod channel;
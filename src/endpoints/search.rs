@@ -0,0 +1,159 @@
+use reqwest;
+use select::document::Document;
+use select::predicate::Attr;
+
+use eksi;
+use slug;
+use endpoints::title::Title;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    /// Newest first
+    Date,
+    Alphabetical,
+    /// Titles with the most entries first
+    Count,
+}
+
+impl SortOrder {
+    fn as_param(&self) -> &'static str {
+        match *self {
+            SortOrder::Date => "Date",
+            SortOrder::Alphabetical => "Topic",
+            SortOrder::Count => "Count",
+        }
+    }
+}
+
+impl Default for SortOrder {
+    fn default() -> SortOrder {
+        SortOrder::Date
+    }
+}
+
+/// Detailed search (detayli arama) query
+///
+/// ```no_run
+/// use eksicli::endpoints::search::{SearchQuery, SortOrder};
+///
+/// let titles = SearchQuery::new("rust")
+///     .author("ssg")
+///     .from("2015-01-01")
+///     .sort(SortOrder::Count)
+///     .titles(0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub keywords: String,
+    pub author: String,
+    /// Dates are in `YYYY-MM-DD` format
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Search the keywords only in the titles, not in the entries
+    pub title_only: bool,
+    pub sort: SortOrder,
+}
+
+impl SearchQuery {
+    pub fn new(keywords: &str) -> SearchQuery {
+        SearchQuery {
+            keywords: keywords.to_string(),
+            ..SearchQuery::default()
+        }
+    }
+
+    pub fn author(mut self, author: &str) -> SearchQuery {
+        self.author = author.to_string();
+        self
+    }
+
+    pub fn from(mut self, date: &str) -> SearchQuery {
+        self.from = Some(date.to_string());
+        self
+    }
+
+    pub fn to(mut self, date: &str) -> SearchQuery {
+        self.to = Some(date.to_string());
+        self
+    }
+
+    pub fn title_only(mut self, title_only: bool) -> SearchQuery {
+        self.title_only = title_only;
+        self
+    }
+
+    pub fn sort(mut self, sort: SortOrder) -> SearchQuery {
+        self.sort = sort;
+        self
+    }
+
+    pub fn to_url(&self, page: usize) -> String {
+        format!(
+            "https://eksisozluk.com/basliklar/ara?SearchForm.Keywords={}&SearchForm.Author={}\
+             &SearchForm.When.From={}&SearchForm.When.To={}&SearchForm.TitleOnly={}\
+             &SearchForm.SortOrder={}&p={}",
+            slug::encode(&self.keywords),
+            slug::encode(&self.author),
+            self.from.as_ref().map_or(String::new(), |x| slug::encode(x)),
+            self.to.as_ref().map_or(String::new(), |x| slug::encode(x)),
+            self.title_only,
+            self.sort.as_param(),
+            page + 1
+        )
+    }
+
+    /// Returns the given page of the matching titles
+    pub fn titles(&self, page: usize) -> Vec<Title> {
+        let text = match reqwest::get(&self.to_url(page)).and_then(|mut x| x.text()) {
+            Ok(text) => text,
+            Err(_) => return vec![],
+        };
+        let doc = Document::from(&text[..]);
+
+        match doc.find(Attr("id", "content")).next() {
+            Some(content) => eksi::titles_of(&content),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_urls() {
+        let base = "https://eksisozluk.com/basliklar/ara?";
+        let cases = vec![
+            (
+                SearchQuery::new("rust"),
+                0,
+                "SearchForm.Keywords=rust&SearchForm.Author=&SearchForm.When.From=&SearchForm.When.To=\
+                 &SearchForm.TitleOnly=false&SearchForm.SortOrder=Date&p=1",
+            ),
+            (
+                SearchQuery::new("ışık & gölge").author("ekşi sözlük").title_only(true).sort(SortOrder::Count),
+                2,
+                "SearchForm.Keywords=%C4%B1%C5%9F%C4%B1k%20%26%20g%C3%B6lge&SearchForm.Author=ek%C5%9Fi%20s%C3%B6zl%C3%BCk\
+                 &SearchForm.When.From=&SearchForm.When.To=&SearchForm.TitleOnly=true&SearchForm.SortOrder=Count&p=3",
+            ),
+            (
+                SearchQuery::new("").author("ssg").from("2015-01-01").to("2015-12-31").sort(SortOrder::Alphabetical),
+                0,
+                "SearchForm.Keywords=&SearchForm.Author=ssg&SearchForm.When.From=2015-01-01\
+                 &SearchForm.When.To=2015-12-31&SearchForm.TitleOnly=false&SearchForm.SortOrder=Topic&p=1",
+            ),
+            // Only one end of the range
+            (
+                SearchQuery::new("a").to("2020-02-29"),
+                0,
+                "SearchForm.Keywords=a&SearchForm.Author=&SearchForm.When.From=&SearchForm.When.To=2020-02-29\
+                 &SearchForm.TitleOnly=false&SearchForm.SortOrder=Date&p=1",
+            ),
+        ];
+
+        for (query, page, url) in cases {
+            assert_eq!(query.to_url(page), format!("{}{}", base, url), "{:?}", query);
+        }
+    }
+}
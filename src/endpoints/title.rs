@@ -1,10 +1,145 @@
+use std::collections::HashMap;
 use std::fmt;
 use reqwest;
 use select::document::Document;
 
 use eksi;
-use extensions::UrlConvertable;
-use endpoints::entry::Entry;
+use slug;
+use endpoints::entry::{self, Entry, PAGE_SIZE};
+
+/// Sort and filter modes of the entries in a title
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EntryFilter {
+    /// Every entry, oldest first
+    All,
+    /// Today's entries of a title in gundem
+    Popular,
+    /// Most liked entries of all time (sukela: tumu)
+    Nice,
+    /// Most liked entries of today (sukela: bugun)
+    DailyNice,
+    /// Entries of given author
+    Author(String),
+    /// Entries containing given keywords
+    Search(String),
+    /// Entries written on given day, `YYYY-MM-DD`
+    Day(String),
+    /// Entries with links. The site has no such mode, the unfiltered pages
+    /// are filtered after they are fetched.
+    Links,
+    /// Entries with links to images, filtered like `Links`
+    Images,
+}
+
+/// Hosts whose links are images even without an image extension
+const IMAGE_HOSTS: &[&str] = &[
+    "imgur.com",
+    "hizliresim.com",
+    "resmim.net",
+    "prnt.sc",
+    "i.redd.it",
+    "pbs.twimg.com",
+];
+
+/// Extensions of the image links
+const IMAGE_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png", ".gif", ".webp"];
+
+/// Returns true if given url points to an image, by its extension or host
+fn is_image(url: &str) -> bool {
+    let url = url.to_lowercase();
+    let rest = url.splitn(2, "://").nth(1).unwrap_or(&url[..]);
+    let path = rest.split(|x| x == '?' || x == '#').next().unwrap_or("");
+    let host = path.split('/').next().unwrap_or("");
+
+    IMAGE_EXTENSIONS.iter().any(|x| path.ends_with(x))
+        || IMAGE_HOSTS.iter().any(|x| host == *x || host.ends_with(&format!(".{}", x)))
+}
+
+impl EntryFilter {
+    /// Modes that can be cycled trough, the ones without arguments
+    pub fn cycle() -> Vec<EntryFilter> {
+        vec![
+            EntryFilter::All,
+            EntryFilter::Popular,
+            EntryFilter::Nice,
+            EntryFilter::DailyNice,
+            EntryFilter::Links,
+            EntryFilter::Images,
+        ]
+    }
+
+    /// Returns the mode without arguments with given name, either the one
+    /// of `:sort` or the one it's displayed with
+    pub fn from_name(name: &str) -> Option<EntryFilter> {
+        match name {
+            "all" | "tümü" => Some(EntryFilter::All),
+            "popular" | "bugün" => Some(EntryFilter::Popular),
+            "nice" | "şükela" => Some(EntryFilter::Nice),
+            "dailynice" | "şükela: bugün" => Some(EntryFilter::DailyNice),
+            "links" | "linkler" => Some(EntryFilter::Links),
+            "images" | "görseller" => Some(EntryFilter::Images),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the mode keeps given entry. Only `Links` and `Images`
+    /// drop entries, the others are filtered by the site.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match *self {
+            EntryFilter::Links => !entry.links.is_empty(),
+            EntryFilter::Images => entry.links.iter().any(|x| is_image(x)),
+            _ => true,
+        }
+    }
+
+    /// Returns the query parameters of the mode
+    fn to_query(&self) -> String {
+        match *self {
+            EntryFilter::All => String::new(),
+            EntryFilter::Popular => "&a=popular".to_string(),
+            EntryFilter::Nice => "&a=nice".to_string(),
+            EntryFilter::DailyNice => "&a=dailynice".to_string(),
+            EntryFilter::Author(ref author) => format!("&a=find&author={}", slug::encode(author)),
+            EntryFilter::Search(ref keywords) => format!("&a=search&keywords={}", slug::encode(keywords)),
+            EntryFilter::Day(ref day) => format!("&day={}", slug::encode(day)),
+            EntryFilter::Links | EntryFilter::Images => String::new(),
+        }
+    }
+}
+
+impl Default for EntryFilter {
+    fn default() -> EntryFilter {
+        EntryFilter::All
+    }
+}
+
+impl fmt::Display for EntryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EntryFilter::All => write!(f, "tümü"),
+            EntryFilter::Popular => write!(f, "bugün"),
+            EntryFilter::Nice => write!(f, "şükela"),
+            EntryFilter::DailyNice => write!(f, "şükela: bugün"),
+            EntryFilter::Author(ref author) => write!(f, "@{}", author),
+            EntryFilter::Search(ref keywords) => write!(f, "arama: {}", keywords),
+            EntryFilter::Day(ref day) => write!(f, "{}", day),
+            EntryFilter::Links => write!(f, "linkler"),
+            EntryFilter::Images => write!(f, "görseller"),
+        }
+    }
+}
+
+/// Returns the url of given page of the title with given slug and id, the
+/// slug is used as it is
+pub fn url_of(slug: &str, id: i32, page: usize, filter: &EntryFilter) -> String {
+    format!(
+        "https://eksisozluk.com/{}--{}?p={}{}",
+        slug::encode(slug),
+        id,
+        page + 1,
+        filter.to_query()
+    )
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Title {
@@ -14,24 +149,105 @@ pub struct Title {
 }
 
 impl Title {
-    pub fn to_url(&self, page: usize, popular: bool) -> String {
-         "https://eksisozluk.com".to_string() + "/" +  &self.title
-            .to_lowercase()
-            .replace(" ", "-")
-            .to_url()
-            + &("--".to_string() + &self.id.to_string())
-            + &("?p=".to_string() + &(page + 1).to_string())
-            + if popular { "&a=popular" } else { "" }
-     }
-
-    pub fn entries(&self, page: usize, popular: bool) -> Vec<Entry> {
-        let text = reqwest::get(&self.to_url(page, popular))
-                    .unwrap()
-                    .text()
-                    .unwrap();
+    /// Returns the url path of the title, without the id
+    pub fn slug(&self) -> String {
+        slug::slugify(&self.title)
+    }
+
+    pub fn to_url(&self, page: usize, filter: &EntryFilter) -> String {
+        url_of(&self.slug(), self.id, page, filter)
+    }
+
+    /// Returns the given page of the entries, an empty list if it can't be
+    /// fetched
+    pub fn entries(&self, page: usize, filter: &EntryFilter) -> Vec<Entry> {
+        self.page(page, filter).map_or(vec![], |(entries, _)| entries)
+    }
+
+    /// Returns the given page of the entries and the count of the pages,
+    /// `None` if it can't be fetched. Only the unfiltered pages are numbered,
+    /// a page of `Links` or `Images` is an unfiltered page without the
+    /// entries they drop, it may be empty.
+    pub fn page(&self, page: usize, filter: &EntryFilter) -> Option<(Vec<Entry>, usize)> {
+        let text = reqwest::get(&self.to_url(page, filter))
+            .and_then(|mut x| x.text())
+            .ok()?;
         let doc = Document::from(&text[..]);
 
-        eksi::entries_of(&doc, popular)
+        let mut entries = eksi::entries_of(&doc);
+        if filter.to_query().is_empty() {
+            entry::set_numbers(&mut entries, page * PAGE_SIZE);
+        }
+        entries.retain(|x| filter.matches(x));
+
+        Some((entries, eksi::page_count_of(&doc)))
+    }
+
+    /// Returns the count of the entries with given filter, from the count of
+    /// its pages and its last page
+    fn count(&self, filter: &EntryFilter) -> Option<usize> {
+        let (first, pages) = self.page(0, filter)?;
+        let last = match pages {
+            0 => return None,
+            1 => first.len(),
+            _ => self.page(pages - 1, filter)?.0.len(),
+        };
+
+        Some((pages - 1) * PAGE_SIZE + last)
+    }
+
+    /// Returns the count of the entries before today's. Today's entries
+    /// (`EntryFilter::Popular`) are the last ones of the title, so the nth
+    /// of them is the entry `offset + n`.
+    pub fn popular_offset(&self) -> Option<usize> {
+        let all = self.count(&EntryFilter::All)?;
+        let today = self.count(&EntryFilter::Popular)?;
+
+        all.checked_sub(today)
+    }
+
+    /// Returns the page and the index in it of the first entry written on
+    /// or after given day, `YYYY-MM-DD`. The pages are binary searched by
+    /// the day of their last entries.
+    pub fn first_on(&self, day: &str) -> Option<(usize, usize)> {
+        let mut pages = HashMap::new();
+        let (first, count) = self.page(0, &EntryFilter::All)?;
+        pages.insert(0, first);
+
+        let mut entries_of = |page: usize| -> Vec<Entry> {
+            pages.entry(page)
+                .or_insert_with(|| self.entries(page, &EntryFilter::All))
+                .clone()
+        };
+        let on_or_after = |entry: &Entry| entry.day().map_or(false, |x| x.as_str() >= day);
+
+        // The first page whose last entry is on or after the day
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let middle = (low + high) / 2;
+            if entries_of(middle).last().map_or(false, |x| on_or_after(x)) {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        if low == count {
+            return None;
+        }
+
+        let index = entries_of(low).iter().position(|x| on_or_after(x))?;
+        Some((low, index))
+    }
+
+    /// Returns the given page of the entries written by `author` in this title
+    pub fn entries_by(&self, author: &str, page: usize) -> Vec<Entry> {
+        self.entries(page, &EntryFilter::Author(author.to_string()))
+    }
+
+    /// Returns the given page of the entries containing `keywords` in this title
+    pub fn search_in(&self, keywords: &str, page: usize) -> Vec<Entry> {
+        self.entries(page, &EntryFilter::Search(keywords.to_string()))
     }
 }
 
@@ -43,3 +259,45 @@ impl fmt::Display for Title {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_display() {
+        for filter in EntryFilter::cycle() {
+            assert_eq!(EntryFilter::from_name(&filter.to_string()), Some(filter.clone()), "{}", filter);
+        }
+
+        let cases = &[
+            ("all", Some(EntryFilter::All)),
+            ("popular", Some(EntryFilter::Popular)),
+            ("dailynice", Some(EntryFilter::DailyNice)),
+            ("images", Some(EntryFilter::Images)),
+            ("today", None),
+            ("", None),
+        ];
+        for &(name, ref expected) in cases {
+            assert_eq!(EntryFilter::from_name(name), *expected, "name {:?}", name);
+        }
+    }
+
+    #[test]
+    fn detects_images() {
+        let cases = &[
+            ("https://example.com/a.png", true),
+            ("http://example.com/a.JPG?w=100", true),
+            ("https://i.imgur.com/abc", true),
+            ("https://imgur.com/gallery/abc", true),
+            ("https://prnt.sc/abc#x", true),
+            ("https://example.com/png", false),
+            ("https://example.com/?file=a.png", false),
+            ("https://notimgur.com/abc", false),
+            ("https://eksisozluk.com/rust--1", false),
+        ];
+        for &(url, expected) in cases {
+            assert_eq!(is_image(url), expected, "url {:?}", url);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::str::FromStr;
+
+// self
+use endpoints::entry::{Entry, Spoilers};
+use endpoints::title::Title;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Text,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Format::Markdown => "md",
+            Format::Text => "txt",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "md" | "markdown" => Ok(Format::Markdown),
+            "txt" | "text" => Ok(Format::Text),
+            _ => Err(format!("Unknown export format: {} (md, txt)", s)),
+        }
+    }
+}
+
+/// Renders given title and entries as a single document, spoiler blocks are
+/// handled by `spoilers`
+pub fn export(title: &Title, entries: &[Entry], format: Format, spoilers: Spoilers) -> String {
+    match format {
+        Format::Markdown => {
+            let mut text = format!("# {}\n", title.title);
+            for entry in entries {
+                text += &format!(
+                    "\n{}\n\n*— {}, {} (#{})*\n",
+                    entry.text_with(spoilers).replace("\n", "  \n"),
+                    entry.author.name,
+                    entry.date.trim(),
+                    entry.id
+                );
+            }
+
+            text
+        }
+        Format::Text => {
+            let mut text = format!("{}\n{}\n", title.title, "=".repeat(title.title.chars().count()));
+            for entry in entries {
+                text += &format!(
+                    "\n{}\n\n({}, {})\n",
+                    entry.text_with(spoilers),
+                    entry.author.name,
+                    entry.date.trim()
+                );
+            }
+
+            text
+        }
+    }
+}
+
+/// Returns the default file name for exporting given title
+pub fn file_name(title: &Title, format: Format) -> String {
+    format!("{}--{}.{}", title.slug(), title.id, format.extension())
+}
@@ -0,0 +1,288 @@
+use regex::Regex;
+
+// self
+use config::Config;
+use endpoints::entry::Entry;
+use endpoints::title::Title;
+
+const SECTION: &str = "filter";
+
+/// Client side rules for hiding entries and titles.
+///
+/// Rules are kept under the `[filter]` section of the config file:
+///
+/// ```text
+/// [filter]
+/// author = ssg
+/// author_id = 12345
+/// title = ^(ukrayna|rusya)
+/// keyword = spoiler
+/// ```
+#[derive(Debug)]
+pub struct Filter {
+    /// Blocked author names
+    pub authors: Vec<String>,
+    /// Blocked author ids
+    pub author_ids: Vec<i32>,
+    /// Titles matching any of these are hidden
+    pub titles: Vec<Regex>,
+    /// Entries containing any of these are hidden
+    pub keywords: Vec<String>,
+    /// Disabled filters hide nothing
+    pub enabled: bool,
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter {
+            authors: vec![],
+            author_ids: vec![],
+            titles: vec![],
+            keywords: vec![],
+            enabled: true,
+        }
+    }
+}
+
+impl Filter {
+    /// Reads the rules from given `Config`, skips invalid regexes.
+    pub fn from_config(config: &Config) -> Filter {
+        Filter {
+            authors: config.get_all(SECTION, "author")
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            author_ids: config.get_all(SECTION, "author_id")
+                .into_iter()
+                .filter_map(|x| x.parse().ok())
+                .collect(),
+            titles: config.get_all(SECTION, "title")
+                .into_iter()
+                .filter_map(|x| Regex::new(x).ok())
+                .collect(),
+            keywords: config.get_all(SECTION, "keyword")
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            enabled: true,
+        }
+    }
+
+    /// Writes the rules back into given `Config`, replacing the old ones.
+    pub fn write_to(&self, config: &mut Config) {
+        for key in &["author", "author_id", "title", "keyword"] {
+            config.remove_all(SECTION, key);
+        }
+
+        for x in &self.authors {
+            config.add(SECTION, "author", x);
+        }
+        for x in &self.author_ids {
+            config.add(SECTION, "author_id", &x.to_string());
+        }
+        for x in &self.titles {
+            config.add(SECTION, "title", x.as_str());
+        }
+        for x in &self.keywords {
+            config.add(SECTION, "keyword", x);
+        }
+    }
+
+    pub fn block_author(&mut self, name: &str) {
+        if !self.authors.iter().any(|x| x.to_lowercase() == name.to_lowercase()) {
+            self.authors.push(name.to_string());
+        }
+    }
+
+    /// Returns false if the author wasn't blocked
+    pub fn unblock_author(&mut self, name: &str) -> bool {
+        let len = self.authors.len();
+        self.authors.retain(|x| x.to_lowercase() != name.to_lowercase());
+        len != self.authors.len()
+    }
+
+    pub fn hide_title(&mut self, pattern: &str) -> Result<(), String> {
+        let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+        if !self.titles.iter().any(|x| x.as_str() == pattern) {
+            self.titles.push(regex);
+        }
+
+        Ok(())
+    }
+
+    /// Returns false if there was no such pattern
+    pub fn unhide_title(&mut self, pattern: &str) -> bool {
+        let len = self.titles.len();
+        self.titles.retain(|x| x.as_str() != pattern);
+        len != self.titles.len()
+    }
+
+    pub fn mute(&mut self, keyword: &str) {
+        if !self.keywords.iter().any(|x| x.to_lowercase() == keyword.to_lowercase()) {
+            self.keywords.push(keyword.to_string());
+        }
+    }
+
+    /// Returns false if the keyword wasn't muted
+    pub fn unmute(&mut self, keyword: &str) -> bool {
+        let len = self.keywords.len();
+        self.keywords.retain(|x| x.to_lowercase() != keyword.to_lowercase());
+        len != self.keywords.len()
+    }
+
+    pub fn is_entry_hidden(&self, entry: &Entry) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let author = entry.author.name.to_lowercase();
+        let text = entry.text.to_lowercase();
+
+        self.author_ids.contains(&entry.author.id)
+            || self.authors.iter().any(|x| x.to_lowercase() == author)
+            || self.keywords.iter().any(|x| text.contains(&x.to_lowercase()))
+    }
+
+    pub fn is_title_hidden(&self, title: &Title) -> bool {
+        self.enabled && self.titles.iter().any(|x| x.is_match(&title.title))
+    }
+
+    /// Returns the visible entries and the count of the hidden ones
+    pub fn entries(&self, entries: &[Entry]) -> (Vec<Entry>, usize) {
+        let len = entries.len();
+        let visible: Vec<_> = entries.iter()
+            .filter(|x| !self.is_entry_hidden(x))
+            .cloned()
+            .collect();
+
+        let hidden = len - visible.len();
+        (visible, hidden)
+    }
+
+    /// Returns the visible titles and the count of the hidden ones
+    pub fn titles(&self, titles: &[Title]) -> (Vec<Title>, usize) {
+        let len = titles.len();
+        let visible: Vec<_> = titles.iter()
+            .filter(|x| !self.is_title_hidden(x))
+            .cloned()
+            .collect();
+
+        let hidden = len - visible.len();
+        (visible, hidden)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use endpoints::author::Author;
+
+    fn entry(author: &str, author_id: i32, text: &str) -> Entry {
+        Entry {
+            id: 1,
+            author: Author {
+                id: author_id,
+                name: author.to_string(),
+            },
+            fav_count: "0".to_string(),
+            is_fav: false,
+            text: text.to_string(),
+            date: String::new(),
+            title: None,
+            number: None,
+            links: vec![],
+            spoilers: vec![],
+            footnotes: text.len(),
+            refs: vec![],
+        }
+    }
+
+    fn title(title: &str) -> Title {
+        Title {
+            id: 1,
+            title: title.to_string(),
+            popular_count: None,
+        }
+    }
+
+    #[test]
+    fn reads_config() {
+        let config = Config::parse("
+[filter]
+author = ssg
+author_id = 12345
+author_id = not a number
+title = ^(ukrayna|rusya)
+title = (unclosed
+keyword = spoiler
+# keyword = comment
+");
+        let filter = Filter::from_config(&config);
+
+        assert_eq!(filter.authors, vec!["ssg"]);
+        assert_eq!(filter.author_ids, vec![12345]);
+        assert_eq!(filter.titles.iter().map(|x| x.as_str()).collect::<Vec<_>>(), vec!["^(ukrayna|rusya)"]);
+        assert_eq!(filter.keywords, vec!["spoiler"]);
+
+        let mut written = Config::default();
+        filter.write_to(&mut written);
+        assert_eq!(written.to_string(), "[filter]\nauthor = ssg\nauthor_id = 12345\ntitle = ^(ukrayna|rusya)\nkeyword = spoiler\n");
+    }
+
+    #[test]
+    fn hides_entries() {
+        let config = Config::parse("[filter]\nauthor = SSG\nauthor_id = 42\nkeyword = Spoiler");
+        let filter = Filter::from_config(&config);
+        let cases = &[
+            ("ssg", 1, "merhaba", true),
+            ("başkası", 42, "merhaba", true),
+            ("başkası", 1, "--- SPOILER ---", true),
+            ("başkası", 1, "merhaba", false),
+        ];
+
+        for &(author, id, text, hidden) in cases {
+            assert_eq!(filter.is_entry_hidden(&entry(author, id, text)), hidden, "{} {} {:?}", author, id, text);
+        }
+
+        let entries = vec![entry("ssg", 1, "a"), entry("b", 1, "b"), entry("c", 42, "c")];
+        let (visible, hidden) = filter.entries(&entries);
+        assert_eq!(visible.iter().map(|x| x.text.as_str()).collect::<Vec<_>>(), vec!["b"]);
+        assert_eq!(hidden, 2);
+    }
+
+    #[test]
+    fn hides_titles() {
+        let mut filter = Filter::default();
+        assert!(filter.hide_title("(unclosed").is_err());
+        filter.hide_title("^ukrayna").unwrap();
+
+        let titles = vec![title("ukrayna"), title("rusya ukrayna"), title("rust")];
+        let (visible, hidden) = filter.titles(&titles);
+        assert_eq!(visible.iter().map(|x| x.title.as_str()).collect::<Vec<_>>(), vec!["rusya ukrayna", "rust"]);
+        assert_eq!(hidden, 1);
+
+        filter.enabled = false;
+        assert_eq!(filter.titles(&titles).1, 0);
+        filter.enabled = true;
+
+        assert!(filter.unhide_title("^ukrayna"));
+        assert!(!filter.unhide_title("^ukrayna"));
+        assert_eq!(filter.titles(&titles).1, 0);
+    }
+
+    #[test]
+    fn edits_rules() {
+        let mut filter = Filter::default();
+        filter.block_author("ssg");
+        filter.block_author("SSG");
+        filter.mute("spoiler");
+        filter.mute("Spoiler");
+
+        assert_eq!(filter.authors, vec!["ssg"]);
+        assert_eq!(filter.keywords, vec!["spoiler"]);
+        assert!(filter.unblock_author("Ssg"));
+        assert!(!filter.unblock_author("ssg"));
+        assert!(filter.unmute("SPOILER"));
+        assert!(!filter.unmute("spoiler"));
+    }
+}
@@ -0,0 +1,288 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+/// Count of the lines kept in the history file
+const HISTORY_SIZE: usize = 1000;
+
+/// Command and search history, persisted to
+/// `$XDG_DATA_HOME/eksi-cli/history` or `~/.local/share/eksi-cli/history`.
+#[derive(Debug, Default)]
+pub struct History {
+    /// Oldest first
+    lines: Vec<String>,
+    /// Index of the recalled line while navigating with Up/Down
+    index: Option<usize>,
+    /// The text typed before the navigation started, recalled lines must
+    /// start with it
+    draft: String,
+}
+
+impl History {
+    pub fn path() -> Option<PathBuf> {
+        let base = match env::var("XDG_DATA_HOME") {
+            Ok(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var("HOME").ok()?).join(".local").join("share"),
+        };
+
+        Some(base.join("eksi-cli").join("history"))
+    }
+
+    pub fn load() -> History {
+        let mut text = String::new();
+        let read = History::path()
+            .and_then(|path| File::open(path).ok())
+            .map(|mut file| file.read_to_string(&mut text));
+
+        let mut history = History::default();
+        if let Some(Ok(_)) = read {
+            history.lines = text.lines()
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string())
+                .collect();
+        }
+
+        history
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = History::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "can't find data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let start = self.lines.len().saturating_sub(HISTORY_SIZE);
+        let mut file = File::create(path)?;
+        for line in &self.lines[start..] {
+            writeln!(file, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds a line to the history and saves it. Consecutive duplicates are
+    /// stored once.
+    pub fn push(&mut self, line: &str) -> io::Result<()> {
+        if self.add(line) {
+            self.save()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds a line to the history, returns false if it's too short or the
+    /// same as the last line
+    fn add(&mut self, line: &str) -> bool {
+        self.reset();
+
+        let line = line.trim();
+        if line.len() <= 1 || self.lines.last().map_or(false, |x| x == line) {
+            return false;
+        }
+
+        self.lines.push(line.to_string());
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn get(&self, i: usize) -> &str {
+        &self.lines[i]
+    }
+
+    /// Stops the Up/Down navigation
+    pub fn reset(&mut self) {
+        self.index = None;
+        self.draft.clear();
+    }
+
+    /// Returns the previous line that starts with the text typed before the
+    /// navigation started
+    pub fn prev(&mut self, current: &str) -> Option<&str> {
+        let end = match self.index {
+            Some(i) => i,
+            None => {
+                self.draft = current.to_string();
+                self.lines.len()
+            }
+        };
+
+        let found = (0..end).rev()
+            .find(|&i| self.lines[i].starts_with(&self.draft) && self.lines[i] != current)?;
+        self.index = Some(found);
+        Some(&self.lines[found])
+    }
+
+    /// Returns the next line, or the draft if the navigation is over
+    pub fn next(&mut self) -> Option<String> {
+        let start = self.index? + 1;
+
+        match (start..self.lines.len()).find(|&i| self.lines[i].starts_with(&self.draft)) {
+            Some(i) => {
+                self.index = Some(i);
+                Some(self.lines[i].clone())
+            }
+            None => {
+                let draft = self.draft.clone();
+                self.reset();
+                Some(draft)
+            }
+        }
+    }
+
+    /// Returns the index of the newest line containing `query` before `before`
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        (0..before.min(self.lines.len())).rev()
+            .find(|&i| self.lines[i].contains(query))
+    }
+}
+
+/// State of an incremental reverse search (Ctrl-R)
+#[derive(Debug, Default)]
+pub struct ReverseSearch {
+    pub query: String,
+    /// Index of the matched history line
+    pub found: Option<usize>,
+    /// The command line before the search started, restored on cancel
+    pub original: String,
+}
+
+impl ReverseSearch {
+    pub fn new(original: &str) -> ReverseSearch {
+        ReverseSearch {
+            original: original.to_string(),
+            ..ReverseSearch::default()
+        }
+    }
+
+    /// Searches again from the newest line, called when the query changes
+    pub fn update(&mut self, history: &History) {
+        self.found = history.search(&self.query, history.len());
+    }
+
+    /// Jumps to an older match, keeps the current one if there is none
+    pub fn older(&mut self, history: &History) {
+        let before = self.found.unwrap_or_else(|| history.len());
+        if let Some(i) = history.search(&self.query, before) {
+            self.found = Some(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(lines: &[&str]) -> History {
+        History {
+            lines: lines.iter().map(|x| x.to_string()).collect(),
+            ..History::default()
+        }
+    }
+
+    #[test]
+    fn adds() {
+        let mut history = history(&[]);
+        let cases = &[
+            (":", false),
+            (" :q ", true),
+            // Consecutive duplicates are stored once
+            (":q", false),
+            (":page 2", true),
+            (":q", true),
+        ];
+
+        for &(line, added) in cases {
+            assert_eq!(history.add(line), added, "{:?}", line);
+        }
+        assert_eq!(history.lines, vec![":q", ":page 2", ":q"]);
+    }
+
+    #[test]
+    fn recalls() {
+        let lines = &["ls", ":open rust", ":open go", ":open rust", ":page 2"];
+        // Up with the typed text, or Down, and the recalled line
+        let cases: &[&[(Option<&str>, Option<&str>)]] = &[
+            &[
+                (Some(""), Some(":page 2")),
+                (Some(":page 2"), Some(":open rust")),
+                (Some(":open rust"), Some(":open go")),
+                (Some(":open go"), Some(":open rust")),
+                // The line on the command line isn't recalled again
+                (Some(":open rust"), Some("ls")),
+                (Some("ls"), None),
+            ],
+            // The recalled lines start with the text typed before Up
+            &[
+                (Some(":open"), Some(":open rust")),
+                (Some(":open rust"), Some(":open go")),
+                (Some(":open go"), Some(":open rust")),
+                (Some(":open rust"), None),
+                (None, Some(":open go")),
+                (None, Some(":open rust")),
+                // The typed text comes back at the end
+                (None, Some(":open")),
+                (None, None),
+            ],
+            &[
+                (None, None),
+                (Some("yok"), None),
+            ],
+        ];
+
+        for steps in cases {
+            let mut history = history(lines);
+            for (i, &(up, recalled)) in steps.iter().enumerate() {
+                let line = match up {
+                    Some(current) => history.prev(current).map(|x| x.to_string()),
+                    None => history.next(),
+                };
+                assert_eq!(line.as_ref().map(|x| x.as_str()), recalled, "step {} of {:?}", i, steps);
+            }
+        }
+    }
+
+    #[test]
+    fn resets() {
+        let mut history = history(&[":a", ":b"]);
+        assert_eq!(history.prev(""), Some(":b"));
+        assert_eq!(history.prev(":b"), Some(":a"));
+
+        // Editing the line starts over from the newest one
+        history.reset();
+        assert_eq!(history.prev(":"), Some(":b"));
+        history.add(":c");
+        assert_eq!(history.prev(""), Some(":c"));
+    }
+
+    #[test]
+    fn searches() {
+        let history = history(&[":open rust", ":page 2", ":open go", ":page 3"]);
+        // The query, Ctrl-R presses after it and the found line
+        let cases: &[(&str, usize, Option<usize>)] = &[
+            ("open", 0, Some(2)),
+            ("open", 1, Some(0)),
+            // The oldest match stays
+            ("open", 2, Some(0)),
+            ("pa", 0, Some(3)),
+            ("", 0, Some(3)),
+            ("yok", 0, None),
+            ("yok", 1, None),
+        ];
+
+        for &(query, older, found) in cases {
+            let mut search = ReverseSearch::new(":x");
+            search.query = query.to_string();
+            search.update(&history);
+            for _ in 0..older {
+                search.older(&history);
+            }
+            assert_eq!(search.found, found, "{:?} after {} ctrl-r", query, older);
+            assert_eq!(search.original, ":x");
+        }
+    }
+}
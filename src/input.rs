@@ -0,0 +1,270 @@
+use termion::event::Key;
+
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::widgets::Widget;
+
+use text;
+
+/// A single line text input with readline-like editing
+#[derive(Debug, Default)]
+pub struct Input {
+    text: String,
+    /// Byte index of the cursor, always on a char boundary
+    cursor: usize,
+}
+
+impl Input {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the text and moves the cursor to the end
+    pub fn set(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set("");
+    }
+
+    /// Handles an editing key, returns false if the key is not an editing key
+    pub fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(ch) => self.insert(ch),
+            Key::Backspace | Key::Ctrl('h') => self.backspace(),
+            Key::Delete | Key::Ctrl('d') => self.delete(),
+            Key::Left | Key::Ctrl('b') => self.cursor = self.prev_boundary(self.cursor),
+            Key::Right | Key::Ctrl('f') => self.cursor = self.next_boundary(self.cursor),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.text.len(),
+            Key::Alt('b') => self.cursor = self.word_start(),
+            Key::Alt('f') => self.cursor = self.word_end(),
+            Key::Ctrl('w') => {
+                let start = self.word_start();
+                self.text.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            Key::Alt('d') => {
+                let end = self.word_end();
+                self.text.drain(self.cursor..end);
+            }
+            Key::Ctrl('u') => {
+                self.text.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::Ctrl('k') => self.text.truncate(self.cursor),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn insert(&mut self, ch: char) {
+        self.text.insert(self.cursor, ch);
+        self.cursor += ch.len_utf8();
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            let prev = self.prev_boundary(self.cursor);
+            self.text.drain(prev..self.cursor);
+            self.cursor = prev;
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            let next = self.next_boundary(self.cursor);
+            self.text.drain(self.cursor..next);
+        }
+    }
+
+    fn prev_boundary(&self, i: usize) -> usize {
+        self.text[..i].char_indices().next_back().map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self, i: usize) -> usize {
+        self.text[i..].chars().next().map_or(i, |x| i + x.len_utf8())
+    }
+
+    /// Returns the start of the word before the cursor
+    fn word_start(&self) -> usize {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_right();
+        trimmed.rfind(char::is_whitespace)
+            .map_or(0, |i| i + trimmed[i..].chars().next().unwrap().len_utf8())
+    }
+
+    /// Returns the end of the word after the cursor
+    fn word_end(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let skipped = after.len() - after.trim_left().len();
+        after[skipped..].find(char::is_whitespace)
+            .map_or(self.text.len(), |i| self.cursor + skipped + i)
+    }
+}
+
+/// Draws an `Input` on a single line with a block cursor
+pub struct InputLine<'a> {
+    input: &'a Input,
+    prefix: &'a str,
+    style: Style,
+}
+
+impl<'a> InputLine<'a> {
+    pub fn new(input: &'a Input) -> InputLine<'a> {
+        InputLine {
+            input: input,
+            prefix: "",
+            style: Style::default(),
+        }
+    }
+
+    /// Text that is shown before the input, it can't be edited
+    pub fn prefix(&mut self, prefix: &'a str) -> &mut InputLine<'a> {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn style(&mut self, style: Style) -> &mut InputLine<'a> {
+        self.style = style;
+        self
+    }
+}
+
+impl<'a> Widget for InputLine<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        let line = self.prefix.to_string() + self.input.as_str();
+        let cursor = text::width(self.prefix) + text::width(&self.input.as_str()[..self.input.cursor]);
+
+        // Scroll horizontally to keep the cursor visible
+        let width = area.width as usize;
+        let (visible, offset) = if cursor >= width {
+            text::skip_columns(&line, cursor - width + 1)
+        } else {
+            (line.as_str(), 0)
+        };
+
+        for x in area.x..area.x + area.width {
+            buf.get_mut(x, area.y).set_symbol(" ").set_style(self.style);
+        }
+        buf.set_stringn(area.x, area.y, visible, width, &self.style);
+
+        let cursor_x = area.x + (cursor - offset) as u16;
+        buf.get_mut(cursor_x, area.y)
+            .set_style(self.style.modifier(Modifier::Invert));
+    }
+}
+
+/// A list drawn upwards from the bottom of its area, the first item is the
+/// closest one to the command line
+pub struct Dropdown<'a> {
+    items: &'a [String],
+    selected: Option<usize>,
+    style: Style,
+    highlight_style: Style,
+}
+
+impl<'a> Dropdown<'a> {
+    pub fn new(items: &'a [String]) -> Dropdown<'a> {
+        Dropdown {
+            items: items,
+            selected: None,
+            style: Style::default(),
+            highlight_style: Style::default().modifier(Modifier::Invert),
+        }
+    }
+
+    pub fn select(&mut self, selected: Option<usize>) -> &mut Dropdown<'a> {
+        self.selected = selected;
+        self
+    }
+
+    pub fn style(&mut self, style: Style) -> &mut Dropdown<'a> {
+        self.style = style;
+        self
+    }
+
+    pub fn highlight_style(&mut self, style: Style) -> &mut Dropdown<'a> {
+        self.highlight_style = style;
+        self
+    }
+}
+
+impl<'a> Widget for Dropdown<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        for (i, item) in self.items.iter().take(area.height as usize).enumerate() {
+            let y = area.y + area.height - 1 - i as u16;
+            let style = if self.selected == Some(i) { self.highlight_style } else { self.style };
+
+            for x in area.x..area.x + area.width {
+                buf.get_mut(x, y).set_symbol(" ").set_style(style);
+            }
+            let width = area.width.saturating_sub(2) as usize;
+            buf.set_stringn(area.x + 1, y, &text::truncate(item, width), width, &style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits() {
+        let cases: &[(&str, &[Key], &str, usize)] = &[
+            ("", &[Key::Char('ş'), Key::Char('i')], "şi", 3),
+            ("ışık", &[Key::Backspace], "ışı", 6),
+            ("ışık", &[Key::Left, Key::Left, Key::Backspace], "ıık", 2),
+            ("ışık", &[Key::Home, Key::Delete], "şık", 0),
+            ("ışık", &[Key::Ctrl('a'), Key::Ctrl('f'), Key::Ctrl('d')], "ıık", 2),
+            ("ışık", &[Key::Right, Key::Delete], "ışık", 7),
+            ("", &[Key::Backspace, Key::Left, Key::Delete], "", 0),
+            // Words
+            ("çok güzel şey", &[Key::Alt('b')], "çok güzel şey", 12),
+            ("çok güzel şey", &[Key::Alt('b'), Key::Alt('b')], "çok güzel şey", 5),
+            ("çok güzel şey  ", &[Key::Alt('b')], "çok güzel şey  ", 12),
+            ("çok güzel şey", &[Key::Home, Key::Alt('f')], "çok güzel şey", 4),
+            ("çok güzel şey", &[Key::Home, Key::Alt('f'), Key::Alt('f')], "çok güzel şey", 11),
+            ("çok güzel şey", &[Key::Home, Key::Alt('f'), Key::Alt('f'), Key::Alt('f')], "çok güzel şey", 16),
+            ("çok güzel şey", &[Key::Ctrl('w')], "çok güzel ", 12),
+            ("çok güzel şey ", &[Key::Ctrl('w')], "çok güzel ", 12),
+            ("ışık", &[Key::Ctrl('w')], "", 0),
+            ("çok güzel şey", &[Key::Home, Key::Alt('d')], " güzel şey", 0),
+            ("çok güzel şey", &[Key::Home, Key::Alt('f'), Key::Alt('d')], "çok şey", 4),
+            ("çok güzel şey", &[Key::Alt('b'), Key::Ctrl('u')], "şey", 0),
+            ("çok güzel şey", &[Key::Alt('b'), Key::Ctrl('k')], "çok güzel ", 12),
+            ("çok güzel şey", &[Key::Ctrl('e'), Key::Alt('d')], "çok güzel şey", 16),
+        ];
+
+        for &(text, keys, expected, cursor) in cases {
+            let mut input = Input::default();
+            input.set(text);
+            for &key in keys {
+                assert!(input.handle(key), "{:?} in {:?}", key, text);
+            }
+            assert_eq!((input.as_str(), input.cursor), (expected, cursor), "{:?} in {:?}", keys, text);
+        }
+    }
+
+    #[test]
+    fn ignores_other_keys() {
+        let mut input = Input::default();
+        input.set("ab");
+        for &key in &[Key::Up, Key::Down, Key::Esc, Key::Ctrl('r'), Key::Alt('x')] {
+            assert!(!input.handle(key), "{:?}", key);
+        }
+        assert_eq!((input.as_str(), input.cursor), ("ab", 2));
+    }
+}
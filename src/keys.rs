@@ -0,0 +1,346 @@
+use termion::event::Key;
+
+/// What a key does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Down,
+    Up,
+    Select,
+    Back,
+    SwitchPanel,
+    Top,
+    Bottom,
+    LineDown,
+    LineUp,
+    PageDown,
+    PageUp,
+    NextChannel,
+    PrevChannel,
+    Debe,
+    FilterTitles,
+    QuickLink,
+    ToggleAuthor,
+    CycleSort,
+    ToggleSpoilers,
+    OpenLink,
+    YankLink,
+    NextReference,
+    PrevReference,
+    Search,
+    DetailedSearch,
+    Find,
+    FindNext,
+    FindPrev,
+    NarrowTitles,
+    WidenTitles,
+    ToggleTitles,
+    Command,
+    Help,
+    Quit,
+    /// Completes the command or the title on the command line
+    Complete,
+    /// Searches the history of the command line
+    HistorySearch,
+    /// Changes the focused field of the search form
+    ChangeField,
+}
+
+/// Where a binding works
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Global,
+    /// The entries pane is focused
+    Entries,
+    /// A single entry is shown, in SingleEntry and Debe modes
+    SingleEntry,
+    /// No title is open
+    StartPage,
+    /// A title or the entries of an author are open
+    TitleOpen,
+    /// The titles are filtered with `F`
+    TitleFilter,
+    /// The command line is edited
+    CommandInput,
+    /// The history is searched with ctrl-r on the command line
+    HistorySearch,
+    /// The detailed search form is open
+    SearchForm,
+}
+
+impl Scope {
+    /// Returns true if the scope is one of the modes that take text, they
+    /// have their own keys
+    pub fn is_input(&self) -> bool {
+        match *self {
+            Scope::TitleFilter | Scope::CommandInput | Scope::HistorySearch | Scope::SearchForm => true,
+            _ => false,
+        }
+    }
+}
+
+/// Groups of the bindings in the help
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Group {
+    Navigation,
+    Titles,
+    Reading,
+    Search,
+    Layout,
+    General,
+    TitleFilter,
+    CommandInput,
+    SearchForm,
+}
+
+impl Group {
+    pub fn all() -> &'static [Group] {
+        &[
+            Group::Navigation, Group::Titles, Group::Reading, Group::Search, Group::Layout, Group::General,
+            Group::TitleFilter, Group::CommandInput, Group::SearchForm,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Group::Navigation => "gezinme",
+            Group::Titles => "basliklar",
+            Group::Reading => "okuma",
+            Group::Search => "arama",
+            Group::Layout => "gorunum",
+            Group::General => "genel",
+            Group::TitleFilter => "baslik suzme (F)",
+            Group::CommandInput => "komut satiri (:)",
+            Group::SearchForm => "detayli arama formu (S)",
+        }
+    }
+}
+
+/// Keys, what they do, where they work, their group and help
+pub type Binding = (&'static [Key], Action, Scope, Group, &'static str);
+
+/// The state of the ui that decides which bindings work
+#[derive(Default)]
+pub struct Context {
+    pub entries: bool,
+    pub single_entry: bool,
+    pub start_page: bool,
+    /// The mode that takes text, only its own bindings work in it
+    pub input: Option<Scope>,
+}
+
+impl Context {
+    /// Returns the context of a mode that takes text
+    pub fn input(scope: Scope) -> Context {
+        Context {
+            input: Some(scope),
+            ..Context::default()
+        }
+    }
+
+    pub fn has(&self, scope: Scope) -> bool {
+        if self.input.is_some() || scope.is_input() {
+            return self.input == Some(scope);
+        }
+
+        match scope {
+            Scope::Global => true,
+            Scope::Entries => self.entries,
+            Scope::SingleEntry => self.entries && self.single_entry,
+            Scope::StartPage => self.start_page,
+            Scope::TitleOpen => !self.start_page,
+            _ => false,
+        }
+    }
+}
+
+/// The keys of the normal mode and of the modes that take text, the first
+/// binding of a key that works in the current context is used. The keys
+/// without a binding in those modes edit the text.
+pub const BINDINGS: &[Binding] = &[
+    (&[Key::Char('?')], Action::Help, Scope::Global, Group::General, "yardim"),
+    (&[Key::Char('q')], Action::Quit, Scope::Global, Group::General, "cik"),
+    (&[Key::Char(':')], Action::Command, Scope::Global, Group::General, "komut"),
+
+    (&[Key::Char('j'), Key::Down], Action::Down, Scope::Global, Group::Navigation, "sonraki baslik/entry"),
+    (&[Key::Char('k'), Key::Up], Action::Up, Scope::Global, Group::Navigation, "onceki baslik/entry"),
+    (&[Key::Char('\n'), Key::Char('l'), Key::Right], Action::Select, Scope::Global, Group::Navigation, "sec, referansi ac"),
+    (&[Key::Esc, Key::Char('h'), Key::Left], Action::Back, Scope::Global, Group::Navigation, "geri"),
+    (&[Key::Char('\t')], Action::SwitchPanel, Scope::Global, Group::Navigation, "panel degistir"),
+    (&[Key::Char('g')], Action::Top, Scope::Entries, Group::Navigation, "basa git"),
+    (&[Key::Char('G')], Action::Bottom, Scope::Entries, Group::Navigation, "sona git"),
+    (&[Key::Ctrl('e')], Action::LineDown, Scope::Entries, Group::Navigation, "bir satir asagi"),
+    (&[Key::Ctrl('y')], Action::LineUp, Scope::Entries, Group::Navigation, "bir satir yukari"),
+    (&[Key::Ctrl('d'), Key::PageDown], Action::PageDown, Scope::Entries, Group::Navigation, "sayfa asagi"),
+    (&[Key::Ctrl('u'), Key::PageUp], Action::PageUp, Scope::Entries, Group::Navigation, "sayfa yukari"),
+
+    (&[Key::Char('c')], Action::NextChannel, Scope::Global, Group::Titles, "sonraki kanal"),
+    (&[Key::Char('C')], Action::PrevChannel, Scope::Global, Group::Titles, "onceki kanal"),
+    (&[Key::Char('D')], Action::Debe, Scope::Global, Group::Titles, "debe"),
+    (&[Key::Char('F')], Action::FilterTitles, Scope::Global, Group::Titles, "basliklari suz"),
+    (&[
+        Key::Char('1'), Key::Char('2'), Key::Char('3'), Key::Char('4'), Key::Char('5'),
+        Key::Char('6'), Key::Char('7'), Key::Char('8'), Key::Char('9')
+    ], Action::QuickLink, Scope::StartPage, Group::Titles, "baslangic sayfasindaki baglanti"),
+
+    (&[Key::Char('a')], Action::ToggleAuthor, Scope::Entries, Group::Reading, "yazarin entry'leri/tumu"),
+    (&[Key::Char('s')], Action::CycleSort, Scope::Global, Group::Reading, "siralama"),
+    (&[Key::Char('r')], Action::ToggleSpoilers, Scope::Entries, Group::Reading, "spoiler ac/kapat"),
+    (&[Key::Char('o')], Action::OpenLink, Scope::Entries, Group::Reading, "link ac"),
+    (&[Key::Char('y')], Action::YankLink, Scope::Entries, Group::Reading, "link kopyala"),
+    (&[Key::Char(']')], Action::NextReference, Scope::SingleEntry, Group::Reading, "sonraki referans"),
+    (&[Key::Char('[')], Action::PrevReference, Scope::SingleEntry, Group::Reading, "onceki referans"),
+
+    (&[Key::Char('/')], Action::Search, Scope::Global, Group::Search, "ara"),
+    (&[Key::Char('S')], Action::DetailedSearch, Scope::Global, Group::Search, "detayli arama"),
+    (&[Key::Char('f')], Action::Find, Scope::TitleOpen, Group::Search, "entry'lerde bul"),
+    (&[Key::Char('n')], Action::FindNext, Scope::TitleOpen, Group::Search, "sonraki eslesme"),
+    (&[Key::Char('N')], Action::FindPrev, Scope::TitleOpen, Group::Search, "onceki eslesme"),
+
+    (&[Key::Char('<')], Action::NarrowTitles, Scope::Global, Group::Layout, "baslik panelini daralt"),
+    (&[Key::Char('>')], Action::WidenTitles, Scope::Global, Group::Layout, "baslik panelini genislet"),
+    (&[Key::Char('t')], Action::ToggleTitles, Scope::Global, Group::Layout, "baslik panelini gizle/goster"),
+
+    (&[Key::Char('\n')], Action::Select, Scope::TitleFilter, Group::TitleFilter, "basligi ac"),
+    (&[Key::Esc], Action::Back, Scope::TitleFilter, Group::TitleFilter, "iptal"),
+    (&[Key::Down, Key::Ctrl('n'), Key::Char('\t')], Action::Down, Scope::TitleFilter, Group::TitleFilter, "sonraki eslesme"),
+    (&[Key::Up, Key::Ctrl('p')], Action::Up, Scope::TitleFilter, Group::TitleFilter, "onceki eslesme"),
+
+    (&[Key::Char('\n')], Action::Select, Scope::CommandInput, Group::CommandInput, "calistir"),
+    (&[Key::Esc], Action::Back, Scope::CommandInput, Group::CommandInput, "iptal"),
+    (&[Key::Char('\t')], Action::Complete, Scope::CommandInput, Group::CommandInput, "komutu ya da basligi tamamla"),
+    (&[Key::Up], Action::Up, Scope::CommandInput, Group::CommandInput, "onceki satir, oneriler acikken yukaridaki oneri"),
+    (&[Key::Down], Action::Down, Scope::CommandInput, Group::CommandInput, "sonraki satir ya da asagidaki oneri"),
+    (&[Key::Ctrl('r')], Action::HistorySearch, Scope::CommandInput, Group::CommandInput, "gecmiste ara"),
+    (&[Key::Ctrl('r')], Action::HistorySearch, Scope::HistorySearch, Group::CommandInput, "gecmiste daha eskisini ara"),
+    (&[Key::Char('\n')], Action::Select, Scope::HistorySearch, Group::CommandInput, "bulunani calistir"),
+    (&[Key::Esc, Key::Ctrl('g')], Action::Back, Scope::HistorySearch, Group::CommandInput, "aramayi iptal et"),
+
+    (&[Key::Char('\n')], Action::Select, Scope::SearchForm, Group::SearchForm, "ara"),
+    (&[Key::Esc], Action::Back, Scope::SearchForm, Group::SearchForm, "iptal"),
+    (&[Key::Char('\t'), Key::Down], Action::Down, Scope::SearchForm, Group::SearchForm, "sonraki alan"),
+    (&[Key::Up], Action::Up, Scope::SearchForm, Group::SearchForm, "onceki alan"),
+    (&[Key::Char(' '), Key::Left, Key::Right], Action::ChangeField, Scope::SearchForm, Group::SearchForm, "secenegi degistir"),
+];
+
+/// The bindings in the one-line help of the footer
+const FOOTER: &[Action] = &[
+    Action::Help, Action::Quit, Action::Command, Action::Select, Action::Back, Action::SwitchPanel,
+    Action::Search,
+];
+
+/// Returns the action of given key in given context
+pub fn action(key: Key, context: &Context) -> Option<Action> {
+    BINDINGS.iter()
+        .find(|x| context.has(x.2) && x.0.contains(&key))
+        .map(|x| x.1)
+}
+
+/// Returns the bindings that work in given context, in the order of the help
+pub fn in_effect(context: &Context) -> Vec<&'static Binding> {
+    BINDINGS.iter()
+        .filter(|x| context.has(x.2))
+        .collect()
+}
+
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\n') => "enter".to_string(),
+        Key::Char('\t') => "tab".to_string(),
+        Key::Char(' ') => "space".to_string(),
+        Key::Char(ch) => ch.to_string(),
+        Key::Ctrl(ch) => format!("ctrl-{}", ch),
+        Key::Alt(ch) => format!("alt-{}", ch),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::PageUp => "pgup".to_string(),
+        Key::PageDown => "pgdn".to_string(),
+        Key::Esc => "esc".to_string(),
+        _ => format!("{:?}", key).to_lowercase(),
+    }
+}
+
+/// Returns the keys of a binding like `j,down`, long runs like the digits
+/// are shortened to `1-9`
+pub fn keys_of(binding: &Binding) -> String {
+    let names: Vec<String> = binding.0.iter().map(|&x| key_name(x)).collect();
+    if names.len() > 3 {
+        format!("{}-{}", names[0], names[names.len() - 1])
+    } else {
+        names.join(",")
+    }
+}
+
+/// Returns the one-line help of the footer, all the keys of the modes that
+/// take text are listed
+pub fn footer(context: &Context) -> String {
+    let help: Vec<String> = BINDINGS.iter()
+        .filter(|x| (context.input.is_some() || FOOTER.contains(&x.1)) && context.has(x.2))
+        .map(|x| format!("{}: {}", key_name(x.0[0]), x.4))
+        .collect();
+
+    help.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    /// Every context the ui can be in
+    fn contexts() -> Vec<Context> {
+        let mut contexts = vec![];
+        for &entries in &[false, true] {
+            for &single_entry in &[false, true] {
+                for &start_page in &[false, true] {
+                    contexts.push(Context {
+                        entries: entries,
+                        single_entry: single_entry,
+                        start_page: start_page,
+                        input: None,
+                    });
+                }
+            }
+        }
+        for &scope in &[Scope::TitleFilter, Scope::CommandInput, Scope::HistorySearch, Scope::SearchForm] {
+            contexts.push(Context::input(scope));
+        }
+
+        contexts
+    }
+
+    /// Returns the binding that handles given key
+    fn binding_of(key: Key, context: &Context) -> Option<&'static Binding> {
+        BINDINGS.iter().find(|x| context.has(x.2) && x.0.contains(&key))
+    }
+
+    #[test]
+    fn help_shows_bindings_in_effect() {
+        for context in contexts() {
+            let help = in_effect(&context);
+            for binding in BINDINGS {
+                let shown = help.iter().any(|x| ptr::eq(*x, binding));
+                let handles = binding.0.iter().any(|&key| binding_of(key, &context).map_or(false, |x| ptr::eq(x, binding)));
+                assert_eq!(shown, handles, "{} ({:?}) in {:?}", keys_of(binding), binding.2, context.input);
+            }
+
+            if context.input.is_none() {
+                assert!(help.iter().all(|x| !x.2.is_input()), "input keys in the help of the normal mode");
+            }
+        }
+    }
+
+    #[test]
+    fn has_no_conflicts() {
+        for context in contexts() {
+            let mut bound = vec![];
+            for binding in in_effect(&context) {
+                for &key in binding.0 {
+                    assert!(!bound.contains(&key), "{} is bound twice, in {:?}", key_name(key), binding.2);
+                    bound.push(key);
+                }
+            }
+        }
+    }
+}
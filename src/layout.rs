@@ -0,0 +1,231 @@
+use std::ops::Range;
+
+use tui::layout::Rect;
+
+use text;
+
+/// Wraps given text into lines of at most `width` columns. Lines are broken
+/// between the words, words longer than a line are broken anywhere.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    wrap_ranges(text, width).into_iter().map(|x| text[x].to_string()).collect()
+}
+
+/// Same as `wrap`, but returns the byte ranges of the lines in given text.
+/// The newlines and the spaces where the lines are broken are left out.
+pub fn wrap_ranges(text: &str, width: usize) -> Vec<Range<usize>> {
+    let mut lines = vec![];
+    if width == 0 {
+        return lines;
+    }
+
+    let mut offset = 0;
+    for paragraph in text.split('\n') {
+        let mut start = offset;
+        let mut len = 0;
+
+        for word in paragraph.split(' ') {
+            let word_len = text::width(word);
+            if len > 0 && len + 1 + word_len > width {
+                lines.push(start..offset - 1);
+                start = offset;
+                len = 0;
+            }
+            if len > 0 {
+                len += 1;
+            } else {
+                start = offset;
+            }
+
+            for (i, ch) in word.char_indices() {
+                let ch_width = text::char_width(ch);
+                if len > 0 && len + ch_width > width {
+                    lines.push(start..offset + i);
+                    start = offset + i;
+                    len = 0;
+                }
+                len += ch_width;
+            }
+            offset += word.len() + 1;
+        }
+
+        lines.push(start..offset - 1);
+    }
+
+    lines
+}
+
+/// Returns true if the cell at `(x, y)` is inside given area
+pub fn contains(area: &Rect, x: u16, y: u16) -> bool {
+    x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height
+}
+
+/// Vertical positions of the entries in a list, in rows
+pub struct ListLayout {
+    heights: Vec<usize>,
+    /// `tops[i]` is the first row of the ith entry
+    tops: Vec<usize>,
+}
+
+impl ListLayout {
+    pub fn new(heights: Vec<usize>) -> ListLayout {
+        let tops = heights.iter()
+            .scan(0, |top, &x| {
+                let current = *top;
+                *top += x;
+                Some(current)
+            })
+            .collect();
+
+        ListLayout {
+            heights: heights,
+            tops: tops,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.heights.len()
+    }
+
+    pub fn top(&self, i: usize) -> usize {
+        self.tops[i]
+    }
+
+    pub fn height(&self, i: usize) -> usize {
+        self.heights[i]
+    }
+
+    /// Total height of the entries
+    pub fn total(&self) -> usize {
+        self.tops.last().map_or(0, |x| x + self.heights[self.heights.len() - 1])
+    }
+
+    /// Limits given offset so that the last page is not scrolled past
+    pub fn clamp(&self, offset: isize, viewport: usize) -> usize {
+        let max = self.total().saturating_sub(viewport);
+        if offset < 0 {
+            0
+        } else {
+            (offset as usize).min(max)
+        }
+    }
+
+    /// Returns the closest offset to given one that shows the ith entry
+    /// fully, or fills the viewport with it if it doesn't fit
+    pub fn reveal(&self, i: usize, offset: usize, viewport: usize) -> usize {
+        if i >= self.count() {
+            return offset;
+        }
+
+        let top = self.top(i);
+        let last_page = (top + self.height(i)).saturating_sub(viewport);
+        offset.max(top.min(last_page)).min(top.max(last_page))
+    }
+
+    /// Returns the entry to focus after scrolling to given offset, the
+    /// current one if it's still visible
+    pub fn focus(&self, current: usize, offset: usize, viewport: usize) -> usize {
+        let visible = |i: usize| self.reveal(i, offset, viewport) == offset;
+        if visible(current) {
+            return current;
+        }
+
+        (0..self.count())
+            .find(|&i| self.top(i) >= offset && visible(i))
+            .or_else(|| self.entry_at(offset))
+            .unwrap_or(current)
+    }
+
+    /// Returns the entry on given row
+    pub fn entry_at(&self, row: usize) -> Option<usize> {
+        (0..self.count()).find(|&i| row >= self.top(i) && row < self.top(i) + self.height(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps() {
+        let cases: &[(&str, usize, &[&str])] = &[
+            ("bir iki üç", 7, &["bir iki", "üç"]),
+            ("bir iki üç", 3, &["bir", "iki", "üç"]),
+            ("bir iki üç", 20, &["bir iki üç"]),
+            ("abc defghij", 4, &["abc", "defg", "hij"]),
+            ("abcdefgh", 3, &["abc", "def", "gh"]),
+            ("a\n\nb", 10, &["a", "", "b"]),
+            ("ışık\nsöz", 3, &["ışı", "k", "söz"]),
+            ("東京 東京", 4, &["東京", "東京"]),
+            ("東京都", 3, &["東", "京", "都"]),
+            ("🎉 🎉🎉", 4, &["🎉", "🎉🎉"]),
+            ("", 5, &[""]),
+            ("rust", 0, &[]),
+        ];
+
+        for &(text, width, lines) in cases {
+            assert_eq!(wrap(text, width), lines, "{:?} in {} columns", text, width);
+        }
+    }
+
+    #[test]
+    fn wraps_ranges() {
+        let cases: &[(&str, usize, &[(usize, usize)])] = &[
+            ("ab cd", 2, &[(0, 2), (3, 5)]),
+            ("ab\ncd", 5, &[(0, 2), (3, 5)]),
+            ("şş şş", 2, &[(0, 4), (5, 9)]),
+            ("abcd", 2, &[(0, 2), (2, 4)]),
+        ];
+
+        for &(text, width, ranges) in cases {
+            let ranges: Vec<Range<usize>> = ranges.iter().map(|&(a, b)| a..b).collect();
+            assert_eq!(wrap_ranges(text, width), ranges, "{:?} in {} columns", text, width);
+        }
+    }
+
+    #[test]
+    fn contains_cells() {
+        let area = Rect { x: 2, y: 1, width: 3, height: 2 };
+        let cases = &[
+            (2, 1, true),
+            (4, 2, true),
+            (5, 1, false),
+            (2, 3, false),
+            (1, 1, false),
+        ];
+
+        for &(x, y, inside) in cases {
+            assert_eq!(contains(&area, x, y), inside, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn lays_out_lists() {
+        let layout = ListLayout::new(vec![3, 5, 2]);
+        assert_eq!((0..3).map(|i| layout.top(i)).collect::<Vec<_>>(), vec![0, 3, 8]);
+        assert_eq!(layout.total(), 10);
+        assert_eq!(ListLayout::new(vec![]).total(), 0);
+
+        // (offset, viewport, clamped)
+        let clamps = &[(-1, 4, 0), (3, 4, 3), (7, 4, 6), (7, 20, 0)];
+        for &(offset, viewport, clamped) in clamps {
+            assert_eq!(layout.clamp(offset, viewport), clamped, "clamp {} in {}", offset, viewport);
+        }
+
+        // (entry, offset, viewport, revealed)
+        let reveals = &[(1, 0, 4, 3), (1, 0, 10, 0), (2, 0, 4, 6), (0, 5, 4, 0), (1, 4, 4, 4), (3, 5, 4, 5)];
+        for &(i, offset, viewport, revealed) in reveals {
+            assert_eq!(layout.reveal(i, offset, viewport), revealed, "reveal {} from {} in {}", i, offset, viewport);
+        }
+
+        // (current, offset, viewport, focused)
+        let focuses = &[(1, 3, 4, 1), (0, 6, 4, 2), (0, 4, 4, 1), (2, 0, 4, 0)];
+        for &(current, offset, viewport, focused) in focuses {
+            assert_eq!(layout.focus(current, offset, viewport), focused, "focus {} at {} in {}", current, offset, viewport);
+        }
+
+        let rows = &[(0, Some(0)), (2, Some(0)), (3, Some(1)), (9, Some(2)), (10, None)];
+        for &(row, entry) in rows {
+            assert_eq!(layout.entry_at(row), entry, "entry at {}", row);
+        }
+    }
+}
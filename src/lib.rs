@@ -1,6 +1,13 @@
 extern crate reqwest;
 extern crate select;
+extern crate regex;
+extern crate serde_json;
 
 pub mod endpoints;
 pub mod eksi;
-mod extensions;
+pub mod config;
+pub mod filter;
+pub mod export;
+pub mod link;
+pub mod matching;
+pub mod slug;
@@ -0,0 +1,228 @@
+//! Parser and builder of the site's urls.
+
+use endpoints::channel::Channel;
+use endpoints::search::{SearchQuery, SortOrder};
+use endpoints::title::{self, EntryFilter};
+use slug;
+
+/// A page of the site that the app can show
+#[derive(Debug, Clone, PartialEq)]
+pub enum Link {
+    /// `/<slug>--<id>`, with `?p=`, `?a=` and `?day=`. `page` is 0-based.
+    Title {
+        slug: String,
+        id: i32,
+        page: usize,
+        filter: EntryFilter,
+    },
+    /// `/entry/<id>`
+    Entry(i32),
+    /// `/biri/<nick>`
+    Author(String),
+    /// `/?q=<query>`
+    Search(String),
+    /// `/basliklar/ara?SearchForm.Keywords=...`
+    DetailedSearch(SearchQuery),
+    /// `/basliklar/gundem`, `/basliklar/kanal/<name>`, `/debe`...
+    Channel(Channel),
+}
+
+impl Link {
+    /// Parses a link of the site. The scheme and the host are optional, so
+    /// `https://eksisozluk.com/entry/1`, `eksisozluk.com/entry/1` and
+    /// `/entry/1` are the same. Returns `None` for the other sites and the
+    /// pages that the app can't show.
+    pub fn parse(url: &str) -> Option<Link> {
+        let url = url.trim();
+        let url = url.split('#').next().unwrap_or("");
+
+        // Strip the scheme and the host, `://` may be in the query too
+        let rest = ["http://", "https://"].iter()
+            .find(|x| url.starts_with(**x))
+            .map_or(url, |x| &url[x.len()..]);
+        let path = if rest.starts_with('/') {
+            rest
+        } else {
+            let (host, path) = match rest.find(|x| x == '/' || x == '?') {
+                Some(i) => (&rest[..i], &rest[i..]),
+                None => (rest, ""),
+            };
+
+            let host = host.to_lowercase();
+            if host != "eksisozluk.com" && !host.ends_with(".eksisozluk.com") {
+                return None;
+            }
+            path
+        };
+
+        let (path, query) = match path.find('?') {
+            Some(i) => (&path[..i], &path[i + 1..]),
+            None => (path, ""),
+        };
+        let params: Vec<(String, String)> = query
+            .split('&')
+            .filter(|x| !x.is_empty())
+            .map(|x| match x.find('=') {
+                Some(i) => (slug::decode(&x[..i]), slug::decode(&x[i + 1..])),
+                None => (slug::decode(x), String::new()),
+            })
+            .collect();
+        let param = |key: &str| {
+            params.iter()
+                .find(|x| x.0 == key)
+                .map(|x| x.1.as_str())
+                .filter(|x| !x.is_empty())
+        };
+
+        let segments: Vec<String> = path
+            .split('/')
+            .filter(|x| !x.is_empty())
+            .map(slug::decode)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(|x| x.as_str()).collect();
+
+        match segments.as_slice() {
+            [] => match param("q") {
+                Some(q) => Some(Link::Search(q.to_string())),
+                None => Some(Link::Channel(Channel::Gundem)),
+            },
+            ["entry", id] => id.parse().ok().map(Link::Entry),
+            ["biri", nick] => Some(Link::Author(nick.to_string())),
+            ["debe"] => Some(Link::Channel(Channel::Debe)),
+            ["basliklar", "ara"] => {
+                let mut query = SearchQuery::new(param("SearchForm.Keywords").unwrap_or(""))
+                    .author(param("SearchForm.Author").unwrap_or(""))
+                    .title_only(param("SearchForm.TitleOnly") == Some("true"))
+                    .sort(match param("SearchForm.SortOrder") {
+                        Some("Topic") => SortOrder::Alphabetical,
+                        Some("Count") => SortOrder::Count,
+                        _ => SortOrder::Date,
+                    });
+                if let Some(from) = param("SearchForm.When.From") {
+                    query = query.from(from);
+                }
+                if let Some(to) = param("SearchForm.When.To") {
+                    query = query.to(to);
+                }
+
+                Some(Link::DetailedSearch(query))
+            }
+            ["basliklar", "kanal", name] => Some(Link::Channel(Channel::Kanal(name.to_string()))),
+            ["basliklar", "tarihte-bugun"] => {
+                Some(Link::Channel(Channel::TarihteBugun(param("year").and_then(|x| x.parse().ok()))))
+            }
+            ["basliklar", name] => Channel::from_name(name)
+                .filter(|x| match *x {
+                    Channel::Kanal(_) | Channel::TarihteBugun(_) => false,
+                    _ => true,
+                })
+                .map(Link::Channel),
+            [title] => {
+                let i = title.rfind("--")?;
+                let id = title[i + 2..].parse().ok()?;
+                let page = param("p")
+                    .and_then(|x| x.parse::<usize>().ok())
+                    .map_or(0, |x| x.saturating_sub(1));
+
+                let filter = match (param("a"), param("day")) {
+                    (Some("popular"), _) => EntryFilter::Popular,
+                    (Some("nice"), _) => EntryFilter::Nice,
+                    (Some("dailynice"), _) => EntryFilter::DailyNice,
+                    (Some("find"), _) => param("author")
+                        .map_or(EntryFilter::All, |x| EntryFilter::Author(x.to_string())),
+                    (Some("search"), _) => param("keywords")
+                        .map_or(EntryFilter::All, |x| EntryFilter::Search(x.to_string())),
+                    (_, Some(day)) => EntryFilter::Day(day.to_string()),
+                    _ => EntryFilter::All,
+                };
+
+                Some(Link::Title {
+                    slug: title[..i].to_string(),
+                    id: id,
+                    page: page,
+                    filter: filter,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the full url of the link
+    pub fn to_url(&self) -> String {
+        match *self {
+            Link::Title { ref slug, id, page, ref filter } => title::url_of(slug, id, page, filter),
+            Link::Entry(id) => format!("https://eksisozluk.com/entry/{}", id),
+            Link::Author(ref nick) => format!("https://eksisozluk.com/biri/{}", slug::encode(nick)),
+            Link::Search(ref query) => format!("https://eksisozluk.com/?q={}", slug::encode(query)),
+            Link::DetailedSearch(ref query) => query.to_url(0),
+            Link::Channel(ref channel) => channel.to_url(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses() {
+        let title = |slug: &str, id, page, filter| Link::Title {
+            slug: slug.to_string(),
+            id: id,
+            page: page,
+            filter: filter,
+        };
+
+        let cases = vec![
+            ("https://eksisozluk.com/rust--2345", Some(title("rust", 2345, 0, EntryFilter::All))),
+            ("eksisozluk.com/rust--2345?p=3", Some(title("rust", 2345, 2, EntryFilter::All))),
+            ("/rust--2345?a=popular", Some(title("rust", 2345, 0, EntryFilter::Popular))),
+            ("/c--1?a=nice#anchor", Some(title("c", 1, 0, EntryFilter::Nice))),
+            ("/a-b--c--12?day=2015-06-01", Some(title("a-b--c", 12, 0, EntryFilter::Day("2015-06-01".to_string())))),
+            ("/x--1?a=find&author=ssg", Some(title("x", 1, 0, EntryFilter::Author("ssg".to_string())))),
+            ("/x--1?a=search&keywords=http%3A%2F%2F", Some(title("x", 1, 0, EntryFilter::Search("http://".to_string())))),
+            ("/x--1?a=search&keywords=bir+iki", Some(title("x", 1, 0, EntryFilter::Search("bir iki".to_string())))),
+            ("https://eksisozluk.com/entry/1", Some(Link::Entry(1))),
+            ("https://eksisozluk.com/entry/abc", None),
+            ("https://eksisozluk.com/biri/ssg", Some(Link::Author("ssg".to_string()))),
+            ("https://eksisozluk.com/biri/a%C5%9Fk", Some(Link::Author("aşk".to_string()))),
+            ("https://eksisozluk.com/?q=rust+dili", Some(Link::Search("rust dili".to_string()))),
+            ("/?q=http://x", Some(Link::Search("http://x".to_string()))),
+            ("https://eksisozluk.com/?q=a://b", Some(Link::Search("a://b".to_string()))),
+            ("eksisozluk.com/?q=a://b", Some(Link::Search("a://b".to_string()))),
+            ("https://eksisozluk.com/", Some(Link::Channel(Channel::Gundem))),
+            ("https://eksisozluk.com/debe", Some(Link::Channel(Channel::Debe))),
+            ("https://eksisozluk.com/basliklar/bugun?p=2", Some(Link::Channel(Channel::Bugun))),
+            ("https://eksisozluk.com/basliklar/kanal/spor", Some(Link::Channel(Channel::Kanal("spor".to_string())))),
+            ("https://eksisozluk.com/basliklar/tarihte-bugun?year=2010", Some(Link::Channel(Channel::TarihteBugun(Some(2010))))),
+            ("https://seyler.eksisozluk.com/rust--1", Some(title("rust", 1, 0, EntryFilter::All))),
+            ("https://example.com/rust--1", None),
+            ("https://eksisozluk.com/rust", None),
+            ("rust", None),
+        ];
+
+        for (url, link) in cases {
+            assert_eq!(Link::parse(url), link, "link of {:?}", url);
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let links = vec![
+            Link::Title { slug: "rust".to_string(), id: 1, page: 4, filter: EntryFilter::Nice },
+            Link::Title { slug: "rust".to_string(), id: 1, page: 0, filter: EntryFilter::Search("a&b".to_string()) },
+            Link::Title { slug: "rust".to_string(), id: 1, page: 0, filter: EntryFilter::DailyNice },
+            Link::Title { slug: "a-b--c".to_string(), id: 12, page: 1, filter: EntryFilter::All },
+            Link::Entry(42),
+            Link::Author("şişko".to_string()),
+            Link::Search("c++ & rust".to_string()),
+            Link::DetailedSearch(SearchQuery::new("rust").author("ssg").from("2015-01-01").sort(SortOrder::Count)),
+            Link::Channel(Channel::Kanal("spor".to_string())),
+            Link::Channel(Channel::TarihteBugun(Some(2010))),
+        ];
+
+        for link in links {
+            assert_eq!(Link::parse(&link.to_url()), Some(link.clone()), "url of {:?}", link);
+        }
+    }
+}
@@ -1,27 +1,87 @@
 extern crate termion;
 extern crate tui;
+extern crate unicode_width;
 
 extern crate eksicli;
 
-use std::io;
+mod command;
+mod history;
+mod input;
+mod keys;
+mod layout;
+mod opener;
+mod search_form;
+mod spans;
+mod text;
+mod theme;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Write};
+use std::fs::File;
+use std::ops::Range;
 use std::thread;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
-use termion::event::Key;
+use termion::event::{self as term_event, Key, MouseButton, MouseEvent};
 use termion::input::TermRead;
 
 use tui::Terminal;
 use tui::backend::MouseBackend;
 use tui::widgets::{Block, Borders, Paragraph, SelectableList, Widget};
 use tui::layout::{Direction, Group, Rect, Size};
-use tui::style::{Color, Modifier, Style};
+use tui::style::{Modifier, Style};
 
 use eksicli::eksi;
-use eksicli::endpoints::title::Title;
-use eksicli::endpoints::entry::Entry;
+use eksicli::eksi::Suggestions;
+use eksicli::config::Config;
+use eksicli::filter::Filter;
+use eksicli::export;
+use eksicli::link::Link;
+use eksicli::matching;
+use eksicli::matching::FuzzyMatch;
+use eksicli::endpoints::author::Author;
+use eksicli::endpoints::search::SearchQuery;
+use eksicli::endpoints::channel::Channel;
+use eksicli::endpoints::title::{EntryFilter, Title};
+use eksicli::endpoints::entry::{Entry, RefKind, Shown, Spoilers, PAGE_SIZE};
 
+use command::{Command, COMMANDS};
+use history::{History, ReverseSearch};
+use input::{Dropdown, Input, InputLine};
+use keys::{Action, Binding, Context, Group, Scope};
+use layout::ListLayout;
+use opener::Opener;
+use search_form::SearchForm;
+use spans::Spans;
+use theme::{ColorSupport, Theme};
 
-const ENTRY_HEIGHT: u16 = 6;
+/// Terminals narrower than this show one pane at a time
+const NARROW: u16 = 80;
+const MIN_TITLES_WIDTH: u16 = 20;
+/// The titles pane can't make the entries narrower than this
+const MIN_ENTRIES_WIDTH: u16 = 40;
+/// Columns added or removed on each resize of the titles pane
+const RESIZE_STEP: u16 = 4;
+/// Rows scrolled by each turn of the mouse wheel
+const WHEEL_ROWS: usize = 3;
+/// Pages loaded by `n` looking for a match of `:find`, unless it's set
+/// with `[find] pages`
+const FIND_PAGES: usize = 3;
+/// Columns of the help popup, it's narrowed to fit the terminal
+const HELP_WIDTH: u16 = 72;
+/// Count of the links on the start page
+const QUICK_LINKS: usize = 9;
+/// Rows of an entry other than its text: header, footer and border
+const ENTRY_CHROME: usize = 3;
+/// Count of the titles remembered for completion
+const RECENT_TITLES: usize = 50;
+/// Count of the suggestions shown while typing a search
+const SUGGESTIONS: usize = 10;
+/// Suggestions are requested after the typing stops for this long
+const SUGGEST_DELAY: Duration = Duration::from_millis(250);
 const LOGO: &str = "
         __           .__                 .__  .__
   ____ |  | __  _____|__|           ____ |  | |__|
@@ -33,96 +93,1487 @@ _/ __ \\|  |/ / /  ___/  |  ______ _/ ___\\|  | |  |
 
 enum Event {
     Input(Key),
+    Mouse(MouseEvent),
+    /// Autocomplete results for the query
+    Suggestions(String, Suggestions),
 }
 
+#[derive(PartialEq)]
 enum ShowMode {
     SingleEntry,
     EntryList,
+    /// Single entries of debe, with their titles
+    Debe,
 }
 
 #[derive(PartialEq)]
 enum Mode {
     Normal,
     CommandInput,
+    SearchForm,
+    TitleFilter,
 }
 
-enum Panel {
-    Titles,
-    Entries,
-}
+#[derive(PartialEq)]
+enum Panel {
+    Titles,
+    Entries,
+}
+
+pub struct App {
+    quit: bool,
+    size: Rect,
+    /// Vector of titles that are shown in title panel
+    titles: Vec<Title>,
+    /// Vector of entries that are shown in entry panel
+    entries: Vec<Entry>,
+    /// Every loaded title, with the ones hidden by the filter
+    loaded_titles: Vec<Title>,
+    /// Every loaded entry, with the ones hidden by the filter
+    loaded_entries: Vec<Entry>,
+    /// Ids of the entries of debe when its channel is open, in order
+    debe: Vec<i32>,
+    /// Count of the titles hidden by the filter
+    titles_hidden: usize,
+    /// Count of the entries hidden by the filter
+    entries_hidden: usize,
+    /// Sort/filter mode of the entries in the current title
+    entry_filter: EntryFilter,
+    /// Currently selected title object
+    title: Option<Title>,
+    /// Nick of the author whose entries are shown instead of a title
+    author: Option<String>,
+    /// Titles opened recently, newest first
+    recent_titles: Vec<String>,
+    /// Index of the currently hovered title
+    title_selected: usize,
+    /// Index of the currently open title
+    title_current: usize,
+    /// Query of the fuzzy filter over the titles
+    title_query: Input,
+    /// Titles that match `title_query` with their matches, the best first
+    title_matches: Vec<(usize, FuzzyMatch)>,
+    /// Selected one of `title_matches`
+    title_match_selected: usize,
+    /// Current page of the left frame
+    title_page: usize,
+    /// Channel shown in the left frame
+    channel: Channel,
+    /// The detailed search whose results are shown in the left frame
+    title_search: Option<SearchQuery>,
+    search_form: SearchForm,
+    /// Index of the currently hovered
+    entry_current: usize,
+    /// Last loaded page of the currently open title
+    entry_page: usize,
+    /// First loaded page of the currently open title
+    entry_first_page: usize,
+    /// First visible row of the entry list
+    entry_scroll: usize,
+    /// First visible row of the entry in SingleEntry and Debe modes
+    entry_line: usize,
+    /// Focused one of the shown references of the entry in SingleEntry and
+    /// Debe modes
+    ref_current: Option<usize>,
+    /// Text of the last `:find`, its matches are highlighted
+    find: Option<String>,
+    /// Whether the entries with given ids match `find`, it's cleared when
+    /// `find` changes
+    find_matches: HashMap<i32, bool>,
+    /// Id of the title whose today's entries are shown and the count of its
+    /// entries before today's, if it could be fetched. It's fetched when the
+    /// numbers are first drawn and cleared when the entries are reloaded.
+    popular_offset: Cell<Option<(i32, Option<usize>)>>,
+    /// Area of the entries in the last draw
+    entries_area: Cell<Rect>,
+    /// Area of the title list in the last draw, empty if it's hidden
+    titles_area: Cell<Rect>,
+    show_mode: ShowMode,
+    mode: Mode,
+    panel_current: Panel,
+    command_buffer: Input,
+    history: History,
+    /// Ongoing Ctrl-R search in the command line
+    reverse_search: Option<ReverseSearch>,
+    /// Autocomplete suggestions of the search being typed
+    suggestions: Vec<String>,
+    suggestion_selected: Option<usize>,
+    /// When to ask for suggestions, set on each keystroke
+    suggest_at: Option<Instant>,
+    /// Completion candidates and the index of the current one
+    completion: Option<(Vec<String>, usize)>,
+    info_buffer: String,
+    config: Config,
+    filter: Filter,
+    opener: Opener,
+    /// An image is printed over the ui, it's hidden with the next key
+    image_shown: bool,
+    /// Width of the titles pane, it's narrowed to fit the terminal
+    titles_width: u16,
+    /// The titles pane is hidden when it's false, the panes are shown one at
+    /// a time
+    show_titles: bool,
+    theme: Theme,
+    /// Ids of the entries with revealed spoilers
+    spoilers_revealed: Vec<i32>,
+    /// The help popup is shown over the panes
+    help_shown: bool,
+    /// First visible line of the help
+    help_scroll: usize,
+}
+
+impl App {
+    fn focus_entries(&mut self) {
+        self.panel_current = Panel::Entries;
+    }
+
+    fn focus_titles(&mut self) {
+        self.panel_current = Panel::Titles;
+    }
+
+    /// Returns the state of the ui that decides which keys work
+    fn key_context(&self) -> Context {
+        Context {
+            entries: self.panel_current == Panel::Entries,
+            single_entry: self.show_mode != ShowMode::EntryList,
+            start_page: self.entries.is_empty(),
+            input: None,
+        }
+    }
+
+    fn update_entries(&mut self, entries: Vec<Entry>) {
+        self.entry_current = 0;
+        self.entry_page = 0;
+        self.entry_first_page = 0;
+        self.entry_scroll = 0;
+        self.entry_line = 0;
+        self.ref_current = None;
+
+        self.entries.clear();
+        self.loaded_entries.clear();
+        self.entries_hidden = 0;
+        self.popular_offset.set(None);
+        self.append_entries(entries);
+    }
+
+    fn append_entries(&mut self, mut entries: Vec<Entry>) {
+        let (mut visible, hidden) = self.filter.entries(&entries);
+        self.entries_hidden += hidden;
+        self.entries.append(&mut visible);
+        self.loaded_entries.append(&mut entries);
+    }
+
+    /// Inserts the entries of a previous page, returns the count of the
+    /// inserted visible entries
+    fn prepend_entries(&mut self, mut entries: Vec<Entry>) -> usize {
+        let (mut visible, hidden) = self.filter.entries(&entries);
+        let count = visible.len();
+        self.entries_hidden += hidden;
+        visible.extend(self.entries.drain(..));
+        self.entries = visible;
+        entries.extend(self.loaded_entries.drain(..));
+        self.loaded_entries = entries;
+
+        count
+    }
+
+    /// Loads the next page of the entries, returns false at the end of the
+    /// title. The pages whose entries are all hidden are skipped.
+    fn load_more_entries(&mut self) -> bool {
+        loop {
+            let entries = match self.fetch_entries(self.entry_page + 1) {
+                Some(entries) => entries,
+                // We are at the end of that title
+                None => return false,
+            };
+
+            let count = self.entries.len();
+            self.entry_page += 1;
+            self.append_entries(entries);
+            if self.entries.len() > count {
+                return true;
+            }
+        }
+    }
+
+    fn append_titles(&mut self, mut titles: Vec<Title>) {
+        let (mut visible, hidden) = self.filter.titles(&titles);
+        self.titles_hidden += hidden;
+        self.titles.append(&mut visible);
+        self.loaded_titles.append(&mut titles);
+    }
+
+    /// Removes the titles of the left frame before another list is loaded
+    fn clear_titles(&mut self) {
+        self.titles.clear();
+        self.loaded_titles.clear();
+        self.titles_hidden = 0;
+        self.title_page = 0;
+        self.title_selected = 0;
+        self.debe.clear();
+    }
+
+    /// Filters the loaded titles and entries again after the rules change,
+    /// the selected ones stay selected if they are still visible
+    fn refilter(&mut self) {
+        let selected = self.titles.get(self.title_selected).cloned();
+        let current = self.titles.get(self.title_current).cloned();
+        let (titles, hidden) = self.filter.titles(&self.loaded_titles);
+        self.titles = titles;
+        self.titles_hidden = hidden;
+
+        let position = |titles: &[Title], title: Option<Title>, old: usize| {
+            titles.iter()
+                .position(|x| Some(x) == title.as_ref())
+                .unwrap_or_else(|| old.min(titles.len().saturating_sub(1)))
+        };
+        self.title_selected = position(&self.titles, selected, self.title_selected);
+        self.title_current = position(&self.titles, current, self.title_current);
+
+        let focused = self.entries.get(self.entry_current).map(|x| x.id);
+        let (entries, hidden) = self.filter.entries(&self.loaded_entries);
+        self.entries = entries;
+        self.entries_hidden = hidden;
+
+        match self.entries.iter().position(|x| Some(x.id) == focused) {
+            Some(i) => self.entry_current = i,
+            None => {
+                self.entry_current = self.entry_current.min(self.entries.len().saturating_sub(1));
+                self.entry_line = 0;
+                self.ref_current = None;
+            }
+        }
+        self.entry_scroll = 0;
+        self.reveal_current();
+    }
+
+    fn open_title(&mut self, title: Title, entries: Vec<Entry>) {
+        self.recent_titles.retain(|x| *x != title.title);
+        self.recent_titles.insert(0, title.title.clone());
+        self.recent_titles.truncate(RECENT_TITLES);
+
+        self.author = None;
+        self.title = Some(title);
+        self.update_entries(entries);
+        if self.show_mode == ShowMode::Debe {
+            self.show_mode = ShowMode::EntryList;
+        }
+        self.focus_entries();
+    }
+
+    /// Fetches the given page of the titles that are currently shown
+    fn fetch_titles(&self, page: usize) -> Vec<Title> {
+        match self.title_search {
+            Some(ref query) => query.titles(page),
+            None => self.channel.titles(page),
+        }
+    }
+
+    /// Replaces the titles with the first page of given channel
+    fn switch_channel(&mut self, channel: Channel) {
+        self.channel = channel;
+        self.title_search = None;
+        self.clear_titles();
+
+        if self.channel == Channel::Debe {
+            // Debe is a list of entries, they are loaded one by one when
+            // they are shown
+            self.debe = eksi::debe_ids();
+            if self.debe.is_empty() {
+                self.info_buffer = "Can't load debe.".to_string();
+            } else {
+                self.open_debe_entry(0);
+            }
+            return;
+        }
+
+        let titles = self.fetch_titles(0);
+        if titles.is_empty() {
+            self.info_buffer = format!("Can't load {}.", self.channel);
+        }
+        self.append_titles(titles);
+        self.focus_titles();
+    }
+
+    /// Returns the links of the start page with the command lines that
+    /// open them: gundem, debe, then the recent titles and searches
+    fn quick_links(&self) -> Vec<(String, String)> {
+        let mut links = vec![
+            ("gundem".to_string(), ":channel gundem".to_string()),
+            ("debe".to_string(), ":debe".to_string()),
+        ];
+
+        let opened = self.recent_titles.iter().map(|x| format!(":open {}", x));
+        let typed = (0..self.history.len()).rev().map(|i| self.history.get(i).to_string());
+        for line in opened.chain(typed) {
+            if links.len() >= QUICK_LINKS {
+                break;
+            }
+
+            let label = if line.starts_with(":open ") {
+                line[":open ".len()..].trim().to_string()
+            } else if line.starts_with('/') {
+                line[1..].trim().to_string()
+            } else {
+                continue;
+            };
+            if !label.is_empty() && !links.iter().any(|x| x.0 == label) {
+                links.push((label, line));
+            }
+        }
+
+        links
+    }
+
+    /// Opens the nth link of the start page, 1-based
+    fn open_quick_link(&mut self, n: usize) {
+        let links = self.quick_links();
+        if let Some(&(_, ref line)) = n.checked_sub(1).and_then(|x| links.get(x)) {
+            match Command::parse(line) {
+                Ok(command) => self.run_command(command),
+                Err(e) => self.info_buffer = e,
+            }
+        }
+    }
+
+    /// Returns the lines of the help: the keys that work in the current
+    /// context by their groups, and the commands. The keys of the modes that
+    /// take text are in the footer while they are used.
+    fn help_lines(&self) -> Vec<Vec<(String, Style)>> {
+        let bindings = keys::in_effect(&self.key_context());
+        let mut commands = vec![("/<sorgu>".to_string(), "sitede ara")];
+        for &(name, args, help) in COMMANDS {
+            commands.push((format!(":{} {}", name, args).trim_right().to_string(), help));
+        }
+
+        let width = bindings.iter()
+            .map(|x| keys::keys_of(x))
+            .chain(commands.iter().map(|x| x.0.clone()))
+            .map(|x| text::width(&x))
+            .max()
+            .unwrap_or(0);
+        let line = |keys: &str, help: &str| vec![
+            (format!("  {}{}  ", keys, " ".repeat(width - text::width(keys))), self.theme.header),
+            (help.to_string(), self.theme.entry_text),
+        ];
+
+        let mut lines = vec![];
+        for &group in Group::all() {
+            let grouped: Vec<&&Binding> = bindings.iter().filter(|x| x.3 == group).collect();
+            if grouped.is_empty() {
+                continue;
+            }
+
+            lines.push(vec![(group.name().to_string(), self.theme.title)]);
+            for binding in grouped {
+                lines.push(line(&keys::keys_of(binding), binding.4));
+            }
+            lines.push(vec![]);
+        }
+
+        lines.push(vec![("komutlar".to_string(), self.theme.title)]);
+        for &(ref usage, help) in &commands {
+            lines.push(line(usage, help));
+        }
+
+        lines
+    }
+
+    /// Returns the area of the help popup with given count of lines, it's
+    /// centered over the panes
+    fn help_area(&self, lines: usize) -> Rect {
+        // The footer is below the panes
+        let height = self.size.height.saturating_sub(1);
+        let popup_width = self.size.width.saturating_sub(4).min(HELP_WIDTH);
+        let popup_height = height.saturating_sub(2).min(lines as u16 + 2);
+
+        Rect {
+            x: (self.size.width - popup_width) / 2,
+            y: (height - popup_height) / 2,
+            width: popup_width,
+            height: popup_height,
+        }
+    }
+
+    fn scroll_help(&mut self, rows: isize) {
+        let lines = self.help_lines().len();
+        let viewport = self.help_area(lines).height.saturating_sub(2) as usize;
+        let max = lines.saturating_sub(viewport);
+
+        let scroll = (self.help_scroll as isize + rows).max(0) as usize;
+        self.help_scroll = scroll.min(max);
+    }
+
+    /// Shows the nth entry of debe, each entry is a page of its own so the
+    /// others are loaded while moving
+    fn open_debe_entry(&mut self, n: usize) {
+        self.title = None;
+        self.author = None;
+        self.update_entries(vec![]);
+        self.entry_page = n;
+        self.entry_first_page = n;
+
+        let entries = self.fetch_entries(n).unwrap_or_default();
+        self.append_entries(entries);
+        if self.entries.is_empty() && !self.load_more_entries() {
+            self.info_buffer = "Can't load the entry.".to_string();
+            return;
+        }
+
+        self.show_mode = ShowMode::Debe;
+        self.focus_entries();
+    }
+
+    /// Switches to the next or the previous channel
+    fn cycle_channel(&mut self, forward: bool) {
+        let channels = Channel::all();
+        let len = channels.len();
+        let current = channels.iter().position(|x| *x == self.channel).unwrap_or(0);
+        let next = if forward { (current + 1) % len } else { (current + len - 1) % len };
+
+        self.switch_channel(channels[next].clone());
+    }
+
+    fn submit_search_form(&mut self) {
+        match self.search_form.query() {
+            Ok(query) => self.run_search(query),
+            Err(e) => self.info_buffer = e,
+        }
+    }
+
+    /// Replaces the titles with the results of given detailed search
+    fn run_search(&mut self, query: SearchQuery) {
+        let titles = query.titles(0);
+        if titles.is_empty() {
+            self.info_buffer = "Nothing found.".to_string();
+            return;
+        }
+
+        self.mode = Mode::Normal;
+        self.clear_titles();
+        self.title_search = Some(query);
+        self.append_titles(titles);
+        self.focus_titles();
+    }
+
+    /// Fetches the given page of the entries that are currently shown, `None`
+    /// after the last page
+    fn fetch_entries(&self, page: usize) -> Option<Vec<Entry>> {
+        let entries = match (self.author.as_ref(), self.title.as_ref()) {
+            (Some(nick), _) => Author::entries_of(nick, page),
+            // The pages of Links and Images may be empty before the last one
+            (None, Some(title)) => return title.page(page, &self.entry_filter)
+                .and_then(|(entries, pages)| if page < pages { Some(entries) } else { None }),
+            (None, None) => self.debe.get(page)
+                .and_then(|&id| eksi::entry(id))
+                .map_or(vec![], |(_, entry)| vec![entry]),
+        };
+
+        if entries.is_empty() {
+            None
+        } else {
+            Some(entries)
+        }
+    }
+
+    fn open_command_input(&mut self, prefix: &str) {
+        self.mode = Mode::CommandInput;
+        self.command_buffer.set(prefix);
+        self.history.reset();
+    }
+
+    fn execute_command(&mut self) {
+        self.mode = Mode::Normal;
+        self.completion = None;
+        self.reverse_search = None;
+        self.close_suggestions();
+        if self.command_buffer.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.history.push(self.command_buffer.as_str()) {
+            self.info_buffer = format!("Can't save history: {}", e);
+        }
+
+        match Command::parse(self.command_buffer.as_str()) {
+            Ok(command) => self.run_command(command),
+            Err(e) => self.info_buffer = e,
+        }
+    }
+
+    /// Opens a link of the focused entry, asks for its number if there are
+    /// more than one
+    fn pick_link(&mut self, command: &str) {
+        let count = self.entries.get(self.entry_current).map_or(0, |x| x.links.len());
+        match count {
+            0 if command == "yank" => self.run_command(Command::Yank(None)),
+            0 => self.info_buffer = "The entry has no links.".to_string(),
+            1 if command == "link" => self.run_command(Command::OpenLink(0)),
+            _ => self.open_command_input(&format!(":{} ", command)),
+        }
+    }
+
+    fn toggle_spoilers(&mut self) {
+        let id = match self.entries.get(self.entry_current) {
+            Some(entry) if !entry.spoilers.is_empty() => entry.id,
+            Some(_) => {
+                self.info_buffer = "The entry has no spoilers.".to_string();
+                return;
+            }
+            None => return,
+        };
+
+        match self.spoilers_revealed.iter().position(|&x| x == id) {
+            Some(i) => {
+                self.spoilers_revealed.remove(i);
+            }
+            None => self.spoilers_revealed.push(id),
+        }
+        // The shown text changes with the spoilers
+        self.find_matches.remove(&id);
+        // The shown references change with the spoilers
+        self.ref_current = None;
+    }
+
+    /// Returns the byte ranges of the shown references of given entry, with
+    /// their styles
+    fn reference_marks(&self, entry: &Entry, index: usize, shown: &Shown) -> Vec<(Range<usize>, Style)> {
+        shown.refs
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, (range, r))| {
+                let style = match entry.refs[r].kind {
+                    RefKind::Bkz(_) => self.theme.bkz,
+                    RefKind::Mention(_) => self.theme.mention,
+                    RefKind::Link(_) => self.theme.link,
+                };
+
+                if index == self.entry_current && self.ref_current == Some(i) {
+                    (range, style.modifier(Modifier::Underline))
+                } else {
+                    (range, style)
+                }
+            })
+            .collect()
+    }
+
+    /// Focuses the next or the previous reference of the focused entry, and
+    /// scrolls the entry to it
+    fn cycle_refs(&mut self, forward: bool) {
+        let width = self.entries_area.get().width.saturating_sub(2) as usize;
+        let rows: Vec<usize> = match self.entries.get(self.entry_current) {
+            Some(entry) => {
+                let shown = self.entry_shown(entry);
+                let lines = layout::wrap_ranges(&shown.text, width);
+
+                // The header is the first row
+                shown.refs
+                    .into_iter()
+                    .map(|(range, _)| lines.iter().position(|x| range.start < x.end).unwrap_or(0) + 1)
+                    .collect()
+            }
+            None => return,
+        };
+        if rows.is_empty() {
+            self.info_buffer = "The entry has no references.".to_string();
+            return;
+        }
+
+        let count = rows.len();
+        let i = match (self.ref_current, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
+        };
+        self.ref_current = Some(i);
+
+        let viewport = self.entries_area.get().height as usize;
+        if rows[i] < self.entry_line {
+            self.entry_line = rows[i];
+        } else if rows[i] >= self.entry_line + viewport {
+            self.entry_line = rows[i] + 1 - viewport;
+        }
+    }
+
+    /// Focuses the next or the previous entry with a match of `:find`,
+    /// starting with the focused one if `inclusive`. Going forward, more
+    /// pages are loaded until a match is found, up to `[find] pages` of them.
+    fn find_next(&mut self, forward: bool, mut inclusive: bool) {
+        let query = match self.find {
+            Some(ref query) => query.clone(),
+            None => {
+                self.info_buffer = "Nothing to find, use :find <text>".to_string();
+                return;
+            }
+        };
+        if self.entries.is_empty() {
+            self.info_buffer = "Open a title to find in its entries.".to_string();
+            return;
+        }
+        let mut pages = self.config.get("find", "pages")
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(FIND_PAGES);
+        let mut current = self.entry_current;
+        let found = loop {
+            let len = self.entries.len();
+            let found = matching::next_index(current, len, forward, inclusive, |i| self.entry_matches(i, &query));
+            if found.is_some() || !forward {
+                break found;
+            }
+
+            // The loaded entries are checked from `len` on
+            if pages > 0 && self.load_more_entries() {
+                pages -= 1;
+                current = len;
+                inclusive = true;
+            } else {
+                break None;
+            }
+        };
+
+        match found {
+            Some(i) => {
+                self.focus_entries();
+                self.entry_current = i;
+
+                let all: Vec<usize> = (0..self.entries.len()).filter(|&i| self.entry_matches(i, &query)).collect();
+                let n = all.iter().position(|&x| x == i).unwrap_or(0) + 1;
+                self.info_buffer = format!("{} ({}/{})", query, n, all.len());
+            }
+            None => self.info_buffer = format!("Pattern not found: {}", query),
+        }
+    }
+
+    /// Returns true if the shown text of the ith entry contains `find`, the
+    /// results are kept until `find` changes
+    fn entry_matches(&mut self, i: usize, query: &str) -> bool {
+        let id = self.entries[i].id;
+        if let Some(&matched) = self.find_matches.get(&id) {
+            return matched;
+        }
+
+        let matched = !matching::find_all(&self.entry_text(&self.entries[i]), query).is_empty();
+        self.find_matches.insert(id, matched);
+        matched
+    }
+
+    /// Opens the focused reference of the focused entry
+    fn open_reference(&mut self) {
+        let kind = match (self.entries.get(self.entry_current), self.ref_current) {
+            (Some(entry), Some(i)) => match self.entry_shown(entry).refs.get(i) {
+                Some(&(_, r)) => entry.refs[r].kind.clone(),
+                None => return,
+            },
+            _ => return,
+        };
+
+        match kind {
+            RefKind::Bkz(query) => self.open_link(Link::Search(query)),
+            RefKind::Mention(nick) => self.open_link(Link::Author(nick)),
+            RefKind::Link(n) => self.run_command(Command::OpenLink(n)),
+        }
+    }
+
+    /// Changes the title to selected one
+    fn open_selected_title(&mut self) {
+        let title = match self.titles.get(self.title_selected) {
+            Some(title) => title.clone(),
+            None => return,
+        };
+        self.entry_filter = self.default_entry_filter(&title);
+        let entries = title.entries(0, &self.entry_filter);
+
+        self.title_current = self.title_selected;
+        self.open_title(title, entries);
+    }
+
+    /// Handles clicks and the wheel, over the panes of the last draw
+    fn mouse(&mut self, event: MouseEvent) {
+        let (button, x, y) = match event {
+            // Termion counts from 1
+            MouseEvent::Press(button, x, y) => (button, x.saturating_sub(1), y.saturating_sub(1)),
+            _ => return,
+        };
+
+        let titles_area = self.titles_area.get();
+        let entries_area = self.entries_area.get();
+        if layout::contains(&titles_area, x, y) {
+            match button {
+                MouseButton::Left => {
+                    // Same offset as the list
+                    let height = titles_area.height as usize;
+                    let offset = (self.title_selected + 1).saturating_sub(height);
+                    let i = offset + (y - titles_area.y) as usize;
+                    if i < self.titles.len() {
+                        self.title_selected = i;
+                        self.open_selected_title();
+                    }
+                }
+                MouseButton::WheelDown => {
+                    self.title_selected = (self.title_selected + WHEEL_ROWS).min(self.titles.len().saturating_sub(1));
+                }
+                MouseButton::WheelUp => {
+                    self.title_selected = self.title_selected.saturating_sub(WHEEL_ROWS);
+                }
+                _ => {}
+            }
+        } else if layout::contains(&entries_area, x, y) && !self.entries.is_empty() {
+            match button {
+                MouseButton::Left => {
+                    let (i, row) = match self.entry_row_at(y - entries_area.y) {
+                        Some(x) => x,
+                        None => return,
+                    };
+                    self.focus_entries();
+                    self.entry_current = i;
+
+                    // Footnotes look like `[n]: url`
+                    let width = entries_area.width.saturating_sub(2) as usize;
+                    let shown = self.entry_shown(&self.entries[i]);
+                    let lines = layout::wrap_ranges(&shown.text, width);
+                    let link = row.checked_sub(1)
+                        .and_then(|x| lines.get(x))
+                        .filter(|x| x.start >= shown.footnotes)
+                        .map(|x| &shown.text[x.clone()])
+                        .filter(|x| x.starts_with('['))
+                        .and_then(|x| x[1..].split("]:").next())
+                        .and_then(|x| x.parse().ok());
+                    if let Some(n) = link {
+                        self.run_command(Command::OpenLink(n));
+                    }
+                }
+                MouseButton::WheelDown | MouseButton::WheelUp => {
+                    let rows = if button == MouseButton::WheelDown {
+                        WHEEL_ROWS as isize
+                    } else {
+                        -(WHEEL_ROWS as isize)
+                    };
+
+                    if self.show_mode == ShowMode::EntryList {
+                        self.scroll_entries(rows);
+                    } else {
+                        self.scroll_entry(rows);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the entry and its row on given row of the entries area
+    fn entry_row_at(&self, y: u16) -> Option<(usize, usize)> {
+        let viewport = self.entries_area.get().height as usize;
+        if self.show_mode == ShowMode::EntryList {
+            let layout = self.entry_layout();
+            let row = layout.clamp(self.entry_scroll as isize, viewport) + y as usize;
+            let i = layout.entry_at(row)?;
+
+            Some((i, row - layout.top(i)))
+        } else {
+            let height = self.entry_height(self.entries.get(self.entry_current)?);
+            let line = self.entry_line.min(height.saturating_sub(viewport));
+
+            Some((self.entry_current, line + y as usize))
+        }
+    }
+
+    /// Returns the text of given entry as it's shown
+    fn entry_text(&self, entry: &Entry) -> String {
+        self.entry_shown(entry).text
+    }
+
+    /// Returns the text of given entry as it's shown, with the positions of
+    /// its spoilers and footnotes
+    fn entry_shown(&self, entry: &Entry) -> Shown {
+        let spoilers = if self.spoilers_revealed.contains(&entry.id) {
+            Spoilers::Keep
+        } else {
+            Spoilers::Collapse
+        };
+
+        entry.shown(spoilers)
+    }
+
+    /// Returns the rows that given entry takes in the area of the last draw
+    fn entry_height(&self, entry: &Entry) -> usize {
+        let width = self.entries_area.get().width.saturating_sub(2) as usize;
+        layout::wrap(&self.entry_text(entry), width).len() + ENTRY_CHROME
+    }
+
+    /// Returns the layout of the entry list for the area of the last draw
+    fn entry_layout(&self) -> ListLayout {
+        ListLayout::new(self.entries.iter().map(|x| self.entry_height(x)).collect())
+    }
+
+    /// Scrolls the focused entry by given rows in SingleEntry and Debe
+    /// modes, returns false if it can't be scrolled that way
+    fn scroll_entry(&mut self, rows: isize) -> bool {
+        let height = match self.entries.get(self.entry_current) {
+            Some(entry) => self.entry_height(entry),
+            None => return false,
+        };
+        let max = height.saturating_sub(self.entries_area.get().height as usize);
+
+        let line = (self.entry_line as isize).saturating_add(rows).max(0) as usize;
+        let line = line.min(max);
+        if line == self.entry_line {
+            return false;
+        }
+
+        self.entry_line = line;
+        true
+    }
+
+    /// Scrolls the entry list so that the focused entry is visible
+    fn reveal_current(&mut self) {
+        let viewport = self.entries_area.get().height as usize;
+        if self.show_mode != ShowMode::EntryList || viewport == 0 {
+            return;
+        }
+
+        self.entry_scroll = self.entry_layout().reveal(self.entry_current, self.entry_scroll, viewport);
+    }
+
+    /// Scrolls the entry list by given rows, the focus moves to a visible
+    /// entry. More entries are loaded at the end of the list.
+    fn scroll_entries(&mut self, rows: isize) {
+        let viewport = self.entries_area.get().height as usize;
+        let mut layout = self.entry_layout();
+        if rows > 0 && self.entry_scroll + viewport + rows as usize > layout.total() && self.load_more_entries() {
+            layout = self.entry_layout();
+        }
+
+        self.entry_scroll = layout.clamp(self.entry_scroll as isize + rows, viewport);
+        self.entry_current = layout.focus(self.entry_current, self.entry_scroll, viewport);
+    }
+
+    /// Shows the page of given link
+    fn open_link(&mut self, link: Link) {
+        match link {
+            Link::Title { slug, id, page, filter } => {
+                let title = Title { id: id, title: slug, popular_count: None };
+                let entries = title.entries(page, &filter);
+
+                // The real title is read from the page
+                let title = match entries.first().and_then(|x| x.title.clone()) {
+                    Some(title) => title,
+                    None => {
+                        self.info_buffer = format!("Can't open {}", title.to_url(page, &filter));
+                        return;
+                    }
+                };
+
+                self.entry_filter = filter;
+                self.open_title(title, entries);
+                self.entry_page = page;
+                self.entry_first_page = page;
+            }
+            Link::Entry(id) => match eksi::entry(id) {
+                Some((title, entry)) => {
+                    self.entry_filter = EntryFilter::All;
+                    self.open_title(title, vec![entry]);
+                    self.show_mode = ShowMode::SingleEntry;
+                }
+                None => self.info_buffer = format!("Can't find entry #{}.", id),
+            },
+            Link::Author(nick) => self.run_command(Command::Author(nick)),
+            Link::Search(query) => self.run_command(Command::Search(query)),
+            Link::DetailedSearch(query) => self.run_search(query),
+            Link::Channel(channel) => self.switch_channel(channel),
+        }
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Search(query) => {
+                match eksi::search(&query) {
+                    Some((title, tentries)) => {
+                        // Update with new data
+                        self.entry_filter = EntryFilter::All;
+                        self.open_title(title, tentries);
+                    }
+                    _ => {
+                        // Show error
+                        self.info_buffer = "Can't find that Baslik.".to_string();
+                    }
+                }
+            }
+            Command::Open(target) => {
+                match Link::parse(&target) {
+                    Some(link) => self.open_link(link),
+                    None if target.contains("://") => self.info_buffer = format!("Can't open {}", target),
+                    None => self.run_command(Command::Search(target)),
+                }
+            }
+            Command::Page(page) => {
+                if self.title.is_none() && self.author.is_none() {
+                    self.info_buffer = "There is no open title.".to_string();
+                    return;
+                }
+
+                let entries = match self.fetch_entries(page - 1) {
+                    Some(entries) => entries,
+                    None => {
+                        self.info_buffer = format!("There is no page {}.", page);
+                        return;
+                    }
+                };
+
+                self.update_entries(entries);
+                self.entry_page = page - 1;
+                self.entry_first_page = page - 1;
+                if self.entries.is_empty() {
+                    self.load_more_entries();
+                }
+            }
+            Command::Goto(n) => {
+                let title = match self.title {
+                    Some(ref title) if self.author.is_none() => title.clone(),
+                    _ => {
+                        self.info_buffer = "There is no open title.".to_string();
+                        return;
+                    }
+                };
+
+                match self.show_ordinal(&title, n) {
+                    None => self.info_buffer = format!("There is no entry #{}.", n),
+                    Some(false) => self.info_buffer = format!("Entry #{} is hidden or doesn't exist.", n),
+                    Some(true) => {}
+                }
+            }
+            Command::Date(day) => {
+                let title = match self.title {
+                    Some(ref title) if self.author.is_none() => title.clone(),
+                    _ => {
+                        self.info_buffer = "There is no open title.".to_string();
+                        return;
+                    }
+                };
+
+                match title.first_on(&day) {
+                    Some((page, i)) => {
+                        let n = page * PAGE_SIZE + i + 1;
+                        if self.show_ordinal(&title, n) != Some(true) {
+                            self.info_buffer = format!("Entry #{} of {} is hidden.", n, day);
+                        }
+                    }
+                    None => self.info_buffer = format!("There are no entries on or after {}.", day),
+                }
+            }
+            Command::Author(nick) => {
+                let entries = Author::entries_of(&nick, 0);
+                if entries.is_empty() {
+                    self.info_buffer = format!("Can't find any entries of {}.", nick);
+                    return;
+                }
+
+                self.author = Some(nick);
+                self.title = None;
+                self.update_entries(entries);
+                self.focus_entries();
+            }
+            Command::Entry(n) => {
+                if n > self.entries.len() {
+                    self.info_buffer = format!("There are only {} entries loaded.", self.entries.len());
+                } else {
+                    self.entry_current = n - 1;
+                    self.focus_entries();
+                }
+            }
+            Command::Sort(filter) => {
+                self.set_entry_filter(filter);
+            }
+            Command::Export(format, spoilers) => {
+                let result = match self.title {
+                    Some(ref title) => {
+                        let path = export::file_name(title, format);
+                        File::create(&path)
+                            .and_then(|mut x| x.write_all(export::export(title, &self.entries, format, spoilers).as_bytes()))
+                            .map(|_| path)
+                    }
+                    None => {
+                        self.info_buffer = "There is no open title.".to_string();
+                        return;
+                    }
+                };
+
+                self.info_buffer = match result {
+                    Ok(path) => format!("Exported to {}", path),
+                    Err(e) => format!("Can't export: {}", e),
+                };
+            }
+            Command::OpenLink(n) => {
+                let url = match self.entries.get(self.entry_current).and_then(|x| x.links.get(n)) {
+                    Some(url) => url.clone(),
+                    None => {
+                        self.info_buffer = format!("There is no link [{}].", n);
+                        return;
+                    }
+                };
+
+                if opener::is_image(&url) {
+                    match self.opener.show_image(&url) {
+                        Ok(true) => {
+                            self.image_shown = true;
+                            return;
+                        }
+                        Ok(false) => {}
+                        Err(e) => {
+                            self.info_buffer = format!("Can't show the image: {}", e);
+                            return;
+                        }
+                    }
+                }
+
+                match self.opener.open(&url) {
+                    Ok(_) => self.info_buffer = format!("Opened {}", url),
+                    Err(e) => self.info_buffer = format!("Can't open {}: {}", url, e),
+                }
+            }
+            Command::Find(query) => {
+                self.find_matches.clear();
+                if query.is_empty() {
+                    self.find = None;
+                } else {
+                    self.find = Some(query);
+                    self.find_next(true, true);
+                }
+            }
+            Command::Yank(n) => {
+                let url = match (self.entries.get(self.entry_current), n) {
+                    (Some(entry), None) => entry.url(),
+                    (Some(entry), Some(n)) if n < entry.links.len() => entry.links[n].clone(),
+                    (Some(_), Some(n)) => {
+                        self.info_buffer = format!("There is no link [{}].", n);
+                        return;
+                    }
+                    (None, _) => {
+                        self.info_buffer = "There is no entry.".to_string();
+                        return;
+                    }
+                };
+
+                self.info_buffer = match opener::yank(&url) {
+                    Ok(_) => format!("Copied {}", url),
+                    Err(e) => format!("Can't copy: {}", e),
+                };
+            }
+            Command::Quit => {
+                self.quit = true;
+            }
+            Command::DetailedSearch => {
+                self.mode = Mode::SearchForm;
+            }
+            Command::Channel(channel) => {
+                self.switch_channel(channel);
+            }
+            Command::Debe => {
+                self.switch_channel(Channel::Debe);
+            }
+            Command::Block(author) => {
+                self.filter.block_author(&author);
+                self.save_filter();
+            }
+            Command::Mute(keyword) => {
+                self.filter.mute(&keyword);
+                self.save_filter();
+            }
+            Command::Hide(pattern) => {
+                match self.filter.hide_title(&pattern) {
+                    Ok(_) => self.save_filter(),
+                    Err(e) => self.info_buffer = format!("Invalid pattern: {}", e),
+                }
+            }
+            Command::Unblock(ref x) | Command::Unmute(ref x) | Command::Unhide(ref x) => {
+                let removed = match command {
+                    Command::Unblock(_) => self.filter.unblock_author(x),
+                    Command::Unmute(_) => self.filter.unmute(x),
+                    _ => self.filter.unhide_title(x),
+                };
+
+                if removed {
+                    self.save_filter();
+                } else {
+                    self.info_buffer = format!("No such rule: {}", x);
+                }
+            }
+            Command::Filter(enabled) => {
+                self.filter.enabled = enabled;
+                self.refilter();
+                self.info_buffer = format!("Filters are {}.", if enabled { "on" } else { "off" });
+            }
+        }
+    }
+
+    /// Loads the unfiltered page of the nth entry of a title and focuses it,
+    /// or the next visible one if it's hidden. The other pages are loaded
+    /// while moving. Returns `None` if the page is empty, `Some(false)` if
+    /// the entry isn't visible.
+    fn show_ordinal(&mut self, title: &Title, n: usize) -> Option<bool> {
+        // Ordinals are only known in the unfiltered pages
+        let page = (n - 1) / PAGE_SIZE;
+        let entries = title.entries(page, &EntryFilter::All);
+        if entries.is_empty() {
+            return None;
+        }
+
+        self.entry_filter = EntryFilter::All;
+        self.update_entries(entries);
+        self.entry_page = page;
+        self.entry_first_page = page;
+        self.focus_entries();
+
+        self.entry_current = self.entries.iter()
+            .position(|x| x.number >= Some(n))
+            .unwrap_or(self.entries.len().saturating_sub(1));
+        self.reveal_current();
+
+        Some(self.entries.get(self.entry_current).and_then(|x| x.number) == Some(n))
+    }
+
+    /// Returns the ordinal of given entry in its title, if it's known.
+    /// Today's entries are numbered by the count of the entries before
+    /// today's, see `popular_offset`.
+    fn entry_number(&self, entry: &Entry) -> Option<usize> {
+        if entry.number.is_some() || self.author.is_some() || self.entry_filter != EntryFilter::Popular {
+            return entry.number;
+        }
+
+        let title = self.title.as_ref()?;
+        let offset = match self.popular_offset.get() {
+            Some((id, offset)) if id == title.id => offset,
+            _ => {
+                let offset = title.popular_offset();
+                self.popular_offset.set(Some((title.id, offset)));
+                offset
+            },
+        }?;
+        let index = self.loaded_entries.iter().position(|x| x.id == entry.id)?;
+
+        Some(offset + self.entry_first_page * PAGE_SIZE + index + 1)
+    }
+
+    /// Default sort mode of a title
+    fn default_entry_filter(&self, title: &Title) -> EntryFilter {
+        // If the title is accessed trough Popular's
+        // it will have a popular_count, so we can
+        // safely get popular entries
+        if title.popular_count.is_some() {
+            EntryFilter::Popular
+        } else {
+            EntryFilter::All
+        }
+    }
+
+    /// Reloads the current title with given sort/filter mode
+    fn set_entry_filter(&mut self, filter: EntryFilter) {
+        if self.title.is_none() || self.author.is_some() {
+            self.info_buffer = "There is no open title.".to_string();
+            return;
+        }
+
+        self.entry_filter = filter;
+        let entries = self.fetch_entries(0).unwrap_or_default();
+        self.update_entries(entries);
+        if self.entries.is_empty() && !self.load_more_entries() {
+            self.info_buffer = format!("No entries for {}.", self.entry_filter);
+        }
+    }
+
+    /// Switches to the next sort mode of the current title
+    fn cycle_entry_filter(&mut self) {
+        let filters = EntryFilter::cycle();
+        let next = filters.iter()
+            .position(|x| *x == self.entry_filter)
+            .map_or(0, |i| (i + 1) % filters.len());
+
+        self.set_entry_filter(filters[next].clone());
+    }
+
+    /// Saves the filter rules and applies them to already loaded items
+    fn save_filter(&mut self) {
+        self.filter.write_to(&mut self.config);
+        if let Err(e) = self.config.save() {
+            self.info_buffer = format!("Can't save config: {}", e);
+        }
+        self.refilter();
+    }
+
+    /// Reads the pane layout from the `[ui]` section of the config
+    fn load_ui(&mut self) {
+        if let Some(width) = self.config.get("ui", "titles_width").and_then(|x| x.parse().ok()) {
+            self.titles_width = width;
+        }
+        if let Some(show) = self.config.get("ui", "show_titles").and_then(|x| x.parse().ok()) {
+            self.show_titles = show;
+        }
+    }
+
+    fn save_ui(&mut self) {
+        self.config.set("ui", "titles_width", &self.titles_width.to_string());
+        self.config.set("ui", "show_titles", &self.show_titles.to_string());
+        if let Err(e) = self.config.save() {
+            self.info_buffer = format!("Can't save config: {}", e);
+        }
+    }
+
+    /// Widens the titles pane, or narrows it if `columns` is negative
+    fn resize_titles(&mut self, columns: i32) {
+        // Start from the width that's actually shown
+        let shown = self.titles_pane_width(self.size.width).unwrap_or(self.titles_width);
+        let width = (shown as i32 + columns).max(MIN_TITLES_WIDTH as i32) as u16;
+        let max = self.size.width.saturating_sub(MIN_ENTRIES_WIDTH).max(MIN_TITLES_WIDTH);
+
+        self.titles_width = width.min(max);
+        self.show_titles = true;
+        self.save_ui();
+    }
+
+    fn toggle_titles(&mut self) {
+        self.show_titles = !self.show_titles;
+        if !self.show_titles {
+            self.focus_entries();
+        }
+        self.save_ui();
+    }
+
+    /// Completes the command line, cycles trough the candidates on each call
+    fn complete_command(&mut self) {
+        if let Some((ref candidates, ref mut i)) = self.completion {
+            *i = (*i + 1) % candidates.len();
+            self.command_buffer.set(&candidates[*i]);
+            return;
+        }
+
+        let candidates = {
+            let mut titles: Vec<&str> = self.recent_titles.iter().map(|x| x.as_str()).collect();
+            for x in &self.titles {
+                if !titles.contains(&x.title.as_str()) {
+                    titles.push(&x.title);
+                }
+            }
+
+            command::complete(self.command_buffer.as_str(), &titles)
+        };
+
+        if candidates.is_empty() {
+            return;
+        }
+
+        self.command_buffer.set(&candidates[0]);
+        if candidates.len() > 1 {
+            self.completion = Some((candidates, 0));
+        }
+    }
+
+    /// Schedules an autocomplete request for the search being typed
+    fn schedule_suggestions(&mut self) {
+        if self.command_buffer.as_str().starts_with('/') && self.command_buffer.as_str().len() > 1 {
+            self.suggest_at = Some(Instant::now());
+        } else {
+            self.close_suggestions();
+        }
+        self.suggestion_selected = None;
+    }
+
+    fn request_suggestions(&mut self, sender: &mpsc::Sender<Event>) {
+        self.suggest_at = None;
+        if !self.command_buffer.as_str().starts_with('/') {
+            return;
+        }
+
+        let query = self.command_buffer.as_str()[1..].to_string();
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let suggestions = eksi::autocomplete(&query);
+            // The receiver is gone if the app is closed meanwhile
+            let _ = sender.send(Event::Suggestions(query, suggestions));
+        });
+    }
+
+    fn show_suggestions(&mut self, query: String, suggestions: Suggestions) {
+        // Ignore the responses of the outdated queries
+        if self.command_buffer.as_str().get(1..) != Some(query.as_str()) {
+            return;
+        }
+
+        self.suggestions = suggestions.titles
+            .into_iter()
+            .chain(suggestions.nicks.into_iter().map(|x| format!("@{}", x)))
+            .take(SUGGESTIONS)
+            .collect();
+        self.suggestion_selected = None;
+    }
 
-// TODO: add command history
-pub struct App {
-    size: Rect,
-    /// Vector of titles that are shown in title panel
-    titles: Vec<Title>,
-    /// Vector of entries that are shown in entry panel
-    entries: Vec<Entry>,
-    /// Show gundem entries?
-    popular: bool,
-    /// Currently selected title object
-    title: Option<Title>,
-    /// Index of the currently hovered title
-    title_selected: usize,
-    /// Index of the currently open title
-    title_current: usize,
-    /// Current page of the left frame
-    title_page: usize,
-    /// Index of the currently hovered
-    entry_current: usize,
-    /// Current page of the currently open title
-    entry_page: usize,
-    show_mode: ShowMode,
-    mode: Mode,
-    panel_current: Panel,
-    command_buffer: String,
-    info_buffer: String,
-}
+    fn close_suggestions(&mut self) {
+        self.suggestions.clear();
+        self.suggestion_selected = None;
+        self.suggest_at = None;
+    }
 
-impl App {
-    fn focus_entries(&mut self) {
-        self.panel_current = Panel::Entries;
+    /// Puts the selected suggestion into the command line, nicks are
+    /// opened with `:author`
+    fn pick_suggestion(&mut self, i: usize) {
+        let line = if self.suggestions[i].starts_with('@') {
+            format!(":author {}", &self.suggestions[i][1..])
+        } else {
+            format!("/{}", self.suggestions[i])
+        };
+
+        self.command_buffer.set(&line);
     }
 
-    fn focus_titles(&mut self) {
-        self.panel_current = Panel::Titles;
+    /// Starts the fuzzy filter over the loaded titles
+    fn open_title_filter(&mut self) {
+        self.mode = Mode::TitleFilter;
+        self.title_query.clear();
+        self.focus_titles();
+        self.match_titles();
     }
 
-    fn update_entries(&mut self, entries: Vec<Entry>) {
-        self.entry_current = 0;
-        self.entry_page = 0;
+    /// Matches the loaded titles to the filter, the best matches come first
+    fn match_titles(&mut self) {
+        let query = self.title_query.as_str();
+        let mut matches: Vec<(usize, FuzzyMatch)> = self.titles.iter()
+            .enumerate()
+            .filter_map(|(i, x)| matching::fuzzy(&x.title, query).map(|m| (i, m)))
+            .collect();
 
-        self.entries = entries;
+        // The sort is stable, the ties stay in the order of the channel
+        matches.sort_by(|a, b| b.1.score.cmp(&a.1.score));
+        self.title_matches = matches;
+        self.title_match_selected = 0;
     }
-    // TODO: maybe replace matching with slice pattern syntax
-    fn execute_command(&mut self) {
-        if self.command_buffer.is_empty() {
-            self.mode = Mode::Normal;
-            return;
+
+    /// Handles keys while filtering the titles
+    fn title_filter_key(&mut self, key: Key) {
+        match keys::action(key, &Context::input(Scope::TitleFilter)) {
+            Some(Action::Back) => {
+                self.mode = Mode::Normal;
+            }
+            Some(Action::Select) => {
+                self.mode = Mode::Normal;
+                if let Some(&(i, _)) = self.title_matches.get(self.title_match_selected) {
+                    self.title_selected = i;
+                    self.open_selected_title();
+                }
+            }
+            Some(Action::Down) => {
+                if self.title_match_selected + 1 < self.title_matches.len() {
+                    self.title_match_selected += 1;
+                }
+            }
+            Some(Action::Up) => {
+                self.title_match_selected = self.title_match_selected.saturating_sub(1);
+            }
+            _ => {
+                if self.title_query.handle(key) {
+                    self.match_titles();
+                }
+            }
         }
+    }
 
-        let first = self.command_buffer.chars().next();
-        match first {
-            Some('/') => {
-                // Search
-                let search_result = eksi::search(&self.command_buffer[1..]);
-                match search_result {
-                    Some((title, tentries)) => {
-                        // Update with new data
-                        self.title = Some(title);
-                        self.update_entries(tentries);
-                    }
-                    _ => {
-                        // Show error
-                        self.info_buffer = "Can't find that Baslik.".to_string();
-                    }
+    /// Handles keys while editing the command line
+    fn command_key(&mut self, key: Key) {
+        match keys::action(key, &Context::input(Scope::CommandInput)) {
+            Some(Action::Back) => {
+                // Quit CommandInput
+                self.mode = Mode::Normal;
+                self.command_buffer.clear();
+                self.completion = None;
+                self.close_suggestions();
+            },
+            Some(Action::Select) => {
+                // (Key::Enter) Execute command
+                if let Some(i) = self.suggestion_selected {
+                    self.pick_suggestion(i);
+                }
+                self.execute_command();
+            },
+            Some(Action::Up) if !self.suggestions.is_empty() => {
+                // Move away from the command line in the dropdown
+                let last = self.suggestions.len() - 1;
+                self.suggestion_selected = Some(self.suggestion_selected.map_or(0, |i| (i + 1).min(last)));
+            },
+            Some(Action::Down) if self.suggestion_selected.is_some() => {
+                self.suggestion_selected = match self.suggestion_selected {
+                    Some(0) | None => None,
+                    Some(i) => Some(i - 1),
+                };
+            },
+            Some(Action::Complete) => {
+                // (Key::Tab) Complete command or title
+                self.complete_command();
+            },
+            Some(Action::Up) => {
+                // Recall older lines starting with what is typed
+                let line = self.history.prev(self.command_buffer.as_str()).map(|x| x.to_string());
+                if let Some(line) = line {
+                    self.command_buffer.set(&line);
+                }
+                self.completion = None;
+            },
+            Some(Action::Down) => {
+                if let Some(line) = self.history.next() {
+                    self.command_buffer.set(&line);
+                }
+                self.completion = None;
+            },
+            Some(Action::HistorySearch) => {
+                self.reverse_search = Some(ReverseSearch::new(self.command_buffer.as_str()));
+                self.completion = None;
+            },
+            _ => {
+                // Edit the command buffer
+                if self.command_buffer.handle(key) {
+                    self.completion = None;
+                    self.history.reset();
+                    self.schedule_suggestions();
                 }
             }
-            Some(_) => {}
-            None => {}
         }
+    }
 
-        self.mode = Mode::Normal;
+    /// Handles keys while searching the history with Ctrl-R
+    fn reverse_search_key(&mut self, key: Key) {
+        let mut search = match self.reverse_search.take() {
+            Some(search) => search,
+            None => return,
+        };
+
+        match (keys::action(key, &Context::input(Scope::HistorySearch)), key) {
+            (Some(Action::HistorySearch), _) => search.older(&self.history),
+            (Some(Action::Select), _) => {
+                self.execute_command();
+                return;
+            },
+            (Some(Action::Back), _) => {
+                // Cancel the search, restore the command line
+                self.command_buffer.set(&search.original);
+                return;
+            },
+            (_, Key::Backspace) => {
+                search.query.pop();
+                search.update(&self.history);
+            },
+            (_, Key::Char(ch)) => {
+                search.query.push(ch);
+                search.update(&self.history);
+            },
+            (_, key) => {
+                // Accept the match and continue editing
+                self.command_buffer.handle(key);
+                return;
+            }
+        }
+
+        if let Some(i) = search.found {
+            self.command_buffer.set(self.history.get(i));
+        }
+        self.reverse_search = Some(search);
     }
 
     fn update_size(&mut self, t: &mut Terminal<MouseBackend>) {
@@ -142,90 +1593,287 @@ impl App {
             .render(t, &size, |t, chunks| {
                 self.draw_content(t, &chunks[0]);
                 self.draw_footer(t, &chunks[1]);
+
+                if self.mode == Mode::CommandInput && !self.suggestions.is_empty() {
+                    self.draw_suggestions(t, &chunks[0]);
+                }
+                if self.help_shown {
+                    self.draw_help(t);
+                }
             });
 
         t.draw()
     }
 
+    /// Draws the help popup over the panes
+    fn draw_help(&self, t: &mut Terminal<MouseBackend>) {
+        let lines = self.help_lines();
+        let area = self.help_area(lines.len());
+        let viewport = area.height.saturating_sub(2) as usize;
+        let scroll = self.help_scroll.min(lines.len().saturating_sub(viewport));
+
+        // Clear the panes under the popup
+        let blank = [(" ".repeat(area.width as usize), Style::default())];
+        for y in area.y..area.y + area.height {
+            Spans::new(&blank).render(t, &Rect { y: y, height: 1, ..area });
+        }
+        Block::default()
+            .borders(Borders::ALL)
+            .title("yardim (j/k: kaydir, diger tuslar: kapat)")
+            .render(t, &area);
+
+        for (i, line) in lines.iter().skip(scroll).take(viewport).enumerate() {
+            let rect = Rect {
+                x: area.x + 1,
+                y: area.y + 1 + i as u16,
+                width: area.width.saturating_sub(2),
+                height: 1,
+            };
+            Spans::new(line).render(t, &rect);
+        }
+    }
+
+    /// Draws the logo and the links of the start page, it's shown when no
+    /// title is open
+    fn draw_start_page(&self, t: &mut Terminal<MouseBackend>, area: &Rect) {
+        let mut lines: Vec<Vec<(String, Style)>> = LOGO.lines()
+            .map(|x| vec![(x.to_string(), self.theme.title)])
+            .collect();
+        lines.push(vec![]);
+
+        for (i, &(ref label, _)) in self.quick_links().iter().enumerate() {
+            if i == 2 {
+                lines.push(vec![]);
+                lines.push(vec![("son gezilenler".to_string(), self.theme.muted)]);
+            }
+            lines.push(vec![
+                (format!("  {}  ", i + 1), self.theme.header),
+                (label.clone(), self.theme.entry_text),
+            ]);
+        }
+
+        lines.push(vec![]);
+        lines.push(vec![("?: tum tuslar ve komutlar".to_string(), self.theme.muted)]);
+
+        for (y, line) in lines.iter().take(area.height as usize).enumerate() {
+            let rect = Rect { y: area.y + y as u16, height: 1, ..*area };
+            Spans::new(line).render(t, &rect);
+        }
+    }
+
+    /// Draws the suggestions on the bottom of given area
+    fn draw_suggestions(&self, t: &mut Terminal<MouseBackend>, area: &Rect) {
+        let height = (self.suggestions.len() as u16).min(area.height);
+        let rect = Rect {
+            x: area.x,
+            y: area.y + area.height - height,
+            width: area.width.min(60),
+            height: height,
+        };
+
+        Dropdown::new(&self.suggestions)
+            .select(self.suggestion_selected)
+            .style(self.theme.popup)
+            .highlight_style(self.theme.selection)
+            .render(t, &rect);
+    }
+
     fn draw_content(&self, t: &mut Terminal<MouseBackend>, area: &Rect) {
-        let titles_str: Vec<_> = self.titles.iter().map(|x| format!("{}", x)).collect();
+        self.titles_area.set(Rect::default());
+        match self.titles_pane_width(area.width) {
+            Some(width) => {
+                Group::default()
+                    .direction(Direction::Horizontal)
+                    .sizes(&[Size::Fixed(width), Size::Percent(100)])
+                    .render(t, area, |t, chunks| {
+                        self.draw_titles(t, &chunks[0]);
+                        self.draw_entries(t, &chunks[1]);
+                    });
+            }
+            // One pane at a time, the search form is in the entries pane
+            None if self.panel_current == Panel::Titles && self.mode != Mode::SearchForm => {
+                self.draw_titles(t, area);
+            }
+            None => self.draw_entries(t, area),
+        }
+    }
+
+    /// Returns the width of the titles pane, `None` if there is room for
+    /// only one pane or the titles pane is hidden
+    fn titles_pane_width(&self, width: u16) -> Option<u16> {
+        if width < NARROW || !self.show_titles {
+            return None;
+        }
+
+        Some(self.titles_width.min(width - MIN_ENTRIES_WIDTH).max(MIN_TITLES_WIDTH))
+    }
 
+    /// Draws the channel switcher and the titles
+    fn draw_titles(&self, t: &mut Terminal<MouseBackend>, area: &Rect) {
         Group::default()
-            .direction(Direction::Horizontal)
-            .sizes(&[Size::Fixed(60), Size::Percent(100)])
+            .direction(Direction::Vertical)
+            .sizes(&[Size::Fixed(1), Size::Percent(100)])
             .render(t, area, |t, chunks| {
-                // Title group
-                SelectableList::default()
-                    .block(Block::default())
-                    .items(&titles_str)
-                    .select(self.title_selected)
-                    .highlight_style(Style::default().modifier(Modifier::Bold))
-                    .highlight_symbol(">")
+                // Channel switcher
+                let header = match self.title_search {
+                    Some(ref query) if query.keywords.is_empty() => format!("arama: @{}", query.author),
+                    Some(ref query) => format!("arama: {}", query.keywords),
+                    None => format!("< {} >", self.channel),
+                };
+                Paragraph::default()
+                    .style(self.theme.header)
+                    .text(&text::truncate(&header, chunks[0].width as usize))
                     .render(t, &chunks[0]);
 
+                // Leave room for the highlight symbol
+                let width = chunks[1].width.saturating_sub(2) as usize;
+                let mut titles_str: Vec<_> = self.titles.iter()
+                    .map(|x| text::truncate(&x.to_string(), width))
+                    .collect();
+                if self.titles_hidden > 0 {
+                    titles_str.push(format!("-- {} gizlendi --", self.titles_hidden));
+                }
 
-                // Title and entry group
-                Group::default()
-                    .direction(Direction::Vertical)
-                    .sizes(&vec![Size::Fixed(2), Size::Percent(100)])
-                    .render(t, &chunks[1], |t, chunks| {
-                        // Draw title
-                        let normal_style = Style::default()
-                            .fg(Color::White)
-                            .modifier(Modifier::Bold);
-                        Paragraph::default()
-                            .wrap(true)
-                            .style(normal_style)
-                            .text(&self.title.as_ref()
-                                  .unwrap_or(&Title {id:1,title: String::new(),popular_count:None}).title)
-                            .render(t, &chunks[0]);
-
-                        if self.entries.is_empty() {
-                            // Draw logo
-                            Paragraph::default()
-                                .style(normal_style)
-                                .text(LOGO)
-                                .render(t, &chunks[1]);
-                        } else {
-                            // Draw entry group
-                            match self.show_mode {
-                                ShowMode::SingleEntry => {
-                                    Group::default()
-                                        .direction(Direction::Vertical)
-                                        .sizes(&vec![Size::Percent(100)])
-                                        .render(t, &chunks[1], |t, chunks| {
-                                            self.draw_entry(
-                                                t,
-                                                &chunks[0],
-                                                &self.entries[self.entry_current],
-                                                self.entry_current,
-                                            );
-                                        });
-                                },
-                                ShowMode::EntryList => {
-                                    let content_height = chunks[1].height;
-                                    let display_count = (content_height / ENTRY_HEIGHT) as usize;
-
-                                    Group::default()
-                                        .direction(Direction::Vertical)
-                                        .sizes(&vec![Size::Fixed(ENTRY_HEIGHT); display_count])
-                                        .render(t, &chunks[1], |t, chunks| {
-                                            for i in 0..self.entries.iter().len() {
-                                                let mut offset = 0;
-                                                if self.entry_current > display_count {
-                                                    offset = self.entry_current - (display_count - 1);
-                                                }
-
-                                                if i < display_count {
-                                                    let index = i + offset;
-                                                    self.draw_entry(t, &chunks[i], &self.entries[index], index);
-                                                }
-                                            }
-                                        });
+                self.titles_area.set(chunks[1]);
+                if self.mode == Mode::TitleFilter {
+                    self.draw_title_matches(t, &chunks[1]);
+                } else {
+                    SelectableList::default()
+                        .block(Block::default())
+                        .items(&titles_str)
+                        .select(self.title_selected)
+                        .highlight_style(self.theme.selection)
+                        .highlight_symbol(">")
+                        .render(t, &chunks[1]);
+                }
+            });
+    }
+
+    /// Draws the titles that match the fuzzy filter, with their matched
+    /// chars highlighted
+    fn draw_title_matches(&self, t: &mut Terminal<MouseBackend>, area: &Rect) {
+        let height = area.height as usize;
+        // Keep the selected one on the screen
+        let offset = (self.title_match_selected + 1).saturating_sub(height);
+
+        let visible = self.title_matches.iter().skip(offset).take(height);
+        for (y, &(i, ref matched)) in visible.enumerate() {
+            let selected = offset + y == self.title_match_selected;
+            let style = if selected { self.theme.selection } else { Style::default() };
+
+            let title = self.titles[i].to_string();
+            let marks: Vec<(Range<usize>, Style)> = matched.positions
+                .iter()
+                .map(|&x| {
+                    let len = title[x..].chars().next().map_or(0, |x| x.len_utf8());
+                    (x..x + len, self.theme.matched)
+                })
+                .collect();
+            let mut line = vec![(if selected { "> " } else { "  " }.to_string(), style)];
+            line.extend(spans::split(&title, 0..title.len(), &marks, style));
+
+            let rect = Rect { y: area.y + y as u16, height: 1, ..*area };
+            Paragraph::default()
+                .style(style)
+                .text("")
+                .render(t, &rect);
+            Spans::new(&line).render(t, &rect);
+        }
+    }
+
+    /// Draws the header and the entries of the open title
+    fn draw_entries(&self, t: &mut Terminal<MouseBackend>, area: &Rect) {
+        Group::default()
+            .direction(Direction::Vertical)
+            .sizes(&vec![Size::Fixed(2), Size::Percent(100)])
+            .render(t, area, |t, chunks| {
+                // Draw title
+                let normal_style = self.theme.title;
+                let mut title_text = match (self.author.as_ref(), self.title.as_ref()) {
+                    _ if self.show_mode == ShowMode::Debe => {
+                        let id = self.entries.get(self.entry_current).map(|x| x.id);
+                        let n = self.debe.iter().position(|&x| Some(x) == id).map_or(0, |i| i + 1);
+                        format!("debe ({}/{})", n, self.debe.len())
+                    },
+                    (Some(nick), _) => format!("@{}", nick),
+                    (None, Some(title)) if self.entry_filter != EntryFilter::All => {
+                        format!("{} [{}]", title.title, self.entry_filter)
+                    },
+                    (None, Some(title)) => title.title.clone(),
+                    (None, None) => String::new(),
+                };
+                if self.entries_hidden > 0 {
+                    title_text += &format!(" ({} gizlendi)", self.entries_hidden);
+                }
+                Paragraph::default()
+                    .wrap(true)
+                    .style(normal_style)
+                    .text(&title_text)
+                    .render(t, &chunks[0]);
+
+                if self.mode == Mode::SearchForm {
+                    self.search_form.draw(t, &chunks[1], &self.theme);
+                } else if self.entries.is_empty() {
+                    self.draw_start_page(t, &chunks[1]);
+                } else {
+                    // Draw entry group
+                    match self.show_mode {
+                        ShowMode::SingleEntry => {
+                            Group::default()
+                                .direction(Direction::Vertical)
+                                .sizes(&vec![Size::Percent(100)])
+                                .render(t, &chunks[1], |t, chunks| {
+                                    self.draw_single_entry(t, &chunks[0]);
+                                });
+                        },
+                        ShowMode::Debe => {
+                            let entry = &self.entries[self.entry_current];
+                            let title_style = self.theme.header;
+
+                            Group::default()
+                                .direction(Direction::Vertical)
+                                .sizes(&vec![Size::Fixed(2), Size::Percent(100)])
+                                .render(t, &chunks[1], |t, chunks| {
+                                    Paragraph::default()
+                                        .wrap(true)
+                                        .style(title_style)
+                                        .text(entry.title.as_ref().map_or("", |x| x.title.as_str()))
+                                        .render(t, &chunks[0]);
+
+                                    self.draw_single_entry(t, &chunks[1]);
+                                });
+                        },
+                        ShowMode::EntryList => {
+                            let area = chunks[1];
+                            self.entries_area.set(area);
+
+                            let viewport = area.height as usize;
+                            let layout = self.entry_layout();
+                            let offset = layout.clamp(self.entry_scroll as isize, viewport);
+
+                            for (i, entry) in self.entries.iter().enumerate() {
+                                let (top, height) = (layout.top(i), layout.height(i));
+                                if top + height <= offset {
+                                    continue;
+                                }
+                                if top >= offset + viewport {
+                                    break;
                                 }
+
+                                // The first entry may be scrolled partially
+                                let skip = offset.saturating_sub(top);
+                                let y = top + skip - offset;
+                                let rect = Rect {
+                                    x: area.x,
+                                    y: area.y + y as u16,
+                                    width: area.width,
+                                    height: (height - skip).min(viewport - y) as u16,
+                                };
+                                self.draw_entry(t, &rect, entry, i, skip);
                             }
                         }
-                    });
+                    }
+                }
             });
     }
 
@@ -233,21 +1881,32 @@ impl App {
         match self.mode {
             Mode::CommandInput => {
                 // Display command input
-                let input_style = Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Yellow);
+                let input_style = self.theme.input;
 
-                Paragraph::default()
-                    .wrap(true)
+                let prefix = match self.reverse_search {
+                    Some(ref search) => format!(
+                        "({}reverse-i-search)`{}': ",
+                        if search.found.is_none() { "failed " } else { "" },
+                        search.query
+                    ),
+                    None => String::new(),
+                };
+
+                InputLine::new(&self.command_buffer)
+                    .prefix(&prefix)
                     .style(input_style)
-                    .text(&self.command_buffer)
                     .render(t, area);
             }
-            Mode::Normal if !self.info_buffer.is_empty() => {
+            Mode::TitleFilter => {
+                let prefix = format!("baslik ({}/{}): ", self.title_matches.len(), self.titles.len());
+                InputLine::new(&self.title_query)
+                    .prefix(&prefix)
+                    .style(self.theme.input)
+                    .render(t, area);
+            }
+            _ if !self.info_buffer.is_empty() => {
                 // Display info/error
-                let info_style = Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Red); // Dark red
+                let info_style = self.theme.error;
 
                 Paragraph::default()
                     .wrap(true)
@@ -255,77 +1914,125 @@ impl App {
                     .text(&self.info_buffer)
                     .render(t, area);
             }
+            Mode::SearchForm => {
+                // The help is shown in the form
+                Paragraph::default()
+                    .style(self.theme.info)
+                    .text("detayli arama")
+                    .render(t, area);
+            }
             Mode::Normal => {
                 // Display simple help
-                let normal_style = Style::default()
-                    .fg(Color::White)
-                    .bg(Color::DarkGray); // Dark blueish
+                let normal_style = self.theme.info;
 
                 Paragraph::default()
                     .wrap(true)
                     .style(normal_style)
-                    .text(&format!(
-                        "/: arama, esc,h: iptal/geri, j: sonraki entry/baslik, k: onceki entry/baslik, enter,l: secim"
-                    ))
+                    .text(&keys::footer(&self.key_context()))
                     .render(t, area);
             }
         }
     }
 
-    fn draw_entry(&self, t: &mut Terminal<MouseBackend>, area: &Rect, entry: &Entry, index: usize) {
+    /// Draws the focused entry scrolled to `entry_line`, with a position
+    /// indicator if it doesn't fit
+    fn draw_single_entry(&self, t: &mut Terminal<MouseBackend>, area: &Rect) {
+        self.entries_area.set(*area);
+
+        let entry = &self.entries[self.entry_current];
+        let height = self.entry_height(entry);
+        let viewport = area.height as usize;
+        let line = self.entry_line.min(height.saturating_sub(viewport));
+        self.draw_entry(t, area, entry, self.entry_current, line);
+
+        if height > viewport {
+            let indicator = format!("[{}-{}/{}]", line + 1, line + viewport, height);
+            let rect = Rect { height: 1, ..*area };
+            Paragraph::default()
+                .style(self.theme.muted)
+                .text(&text::right_aligned(&indicator, rect.width as usize))
+                .render(t, &rect);
+        }
+    }
+
+    /// Draws given entry, `skip` rows of it are scrolled above the area
+    fn draw_entry(&self, t: &mut Terminal<MouseBackend>, area: &Rect, entry: &Entry, index: usize, skip: usize) {
         let title_style = if self.entry_current == index {
-            Style::default().fg(Color::Magenta).modifier(Modifier::Bold)
+            self.theme.entry_header.modifier(Modifier::Bold)
         } else {
-            Style::default().fg(Color::Magenta)
+            self.theme.entry_header
         };
 
-        Block::default()
-            .borders(Borders::BOTTOM)
-            .title(&format!("{}.", index + 1))
-            .title_style(title_style)
-            .render(t, area);
+        let header = match (self.author.as_ref(), entry.title.as_ref(), self.entry_number(entry)) {
+            (Some(_), Some(title), Some(n)) => format!("{}. {}", n, title.title),
+            (Some(_), Some(title), None) => title.title.clone(),
+            (_, _, Some(n)) => format!("{}.", n),
+            _ => String::new(),
+        };
+        let footer = vec![
+            ("(".to_string(), self.theme.entry_text),
+            (entry.author.name.clone(), self.theme.author),
+            (", ".to_string(), self.theme.entry_text),
+            (entry.date.trim().to_string(), self.theme.date),
+            (")".to_string(), self.theme.entry_text),
+        ];
 
-        Group::default()
-            .direction(Direction::Vertical)
-            .sizes(&vec![Size::Percent(99), Size::Fixed(1)])
-            .margin(1)
-            .render(t, area, |t, chunks| {
-                // Entry
-                Paragraph::default()
-                    .wrap(true)
-                    .text(&entry.text)
-                    .render(t, &chunks[0]);
+        // Rows: header, text, footer and the bottom border
+        let shown = self.entry_shown(entry);
+        let content = &shown.text;
+        let lines = layout::wrap_ranges(content, area.width.saturating_sub(2) as usize);
+        let mut marks: Vec<(Range<usize>, Style)> = shown.spoilers.iter()
+            .map(|x| (x.clone(), self.theme.spoiler))
+            .collect();
+        if shown.footnotes < content.len() {
+            marks.push((shown.footnotes..content.len(), self.theme.link));
+        }
+        marks = spans::overlay(marks, self.reference_marks(entry, index, &shown));
+        if let Some(ref query) = self.find {
+            let found = matching::find_all(content, query)
+                .into_iter()
+                .map(|x| (x, self.theme.matched))
+                .collect();
+            marks = spans::overlay(marks, found);
+        }
+        for y in 0..area.height {
+            let row = skip + y as usize;
+            let rect = Rect { x: area.x, y: area.y + y, width: area.width, height: 1 };
+            let inner = Rect { x: area.x + 1, width: area.width.saturating_sub(2), ..rect };
 
+            if row == 0 {
                 Paragraph::default()
-                    .wrap(false)
-                    .text(&text_right_aligned(
-                        &format!(
-                            "({author}, {date})",
-                            author = entry.author.name,
-                            date = entry.date
-                        ),
-                        &chunks[1],
-                    ))
-                    .render(t, &chunks[1]);
-            });
+                    .style(title_style)
+                    .text(&text::truncate(&header, rect.width as usize))
+                    .render(t, &rect);
+            } else if row <= lines.len() {
+                let range = lines[row - 1].clone();
+                Spans::new(&spans::split(content, range, &marks, self.theme.entry_text)).render(t, &inner);
+            } else if row == lines.len() + 1 {
+                Spans::new(&footer)
+                    .right_aligned(true)
+                    .render(t, &inner);
+            } else if row == lines.len() + 2 {
+                Block::default()
+                    .borders(Borders::BOTTOM)
+                    .render(t, &rect);
+            }
+        }
     }
 }
 
-fn text_right_aligned(text: &str, rect: &Rect) -> String {
-    let width = rect.width;
-    let spaces = width as usize - text.len();
-
-    (" ".repeat(spaces) + &text)
-}
-
 fn init_events() -> (mpsc::Sender<Event>, mpsc::Receiver<Event>) {
     let (sender, receiver) = mpsc::channel();
     let input_sender = sender.clone();
 
     thread::spawn(move || {
-        for c in io::stdin().keys() {
-            let key = c.unwrap();
-            input_sender.send(Event::Input(key)).unwrap();
+        for event in io::stdin().events() {
+            let event = match event.unwrap() {
+                term_event::Event::Key(key) => Event::Input(key),
+                term_event::Event::Mouse(mouse) => Event::Mouse(mouse),
+                term_event::Event::Unsupported(_) => continue,
+            };
+            input_sender.send(event).unwrap();
         }
     });
 
@@ -338,164 +2045,426 @@ fn main() {
     term.hide_cursor().unwrap();
 
     let mut app = App {
+        quit: false,
         size: Rect::default(),
         entries: vec![],
         titles: vec![],
-        popular: true,
+        loaded_titles: vec![],
+        loaded_entries: vec![],
+        debe: vec![],
+        titles_hidden: 0,
+        entries_hidden: 0,
+        entry_filter: EntryFilter::All,
         title: None,
+        author: None,
+        recent_titles: vec![],
         title_selected: 0,
+        title_query: Input::default(),
+        title_matches: vec![],
+        title_match_selected: 0,
         title_current: 0,
         title_page: 0,
+        channel: Channel::Gundem,
+        title_search: None,
+        search_form: SearchForm::default(),
         entry_current: 0,
         entry_page: 0,
+        entry_first_page: 0,
+        entry_scroll: 0,
+        entry_line: 0,
+        ref_current: None,
+        find: None,
+        find_matches: HashMap::new(),
+        popular_offset: Cell::new(None),
+        entries_area: Cell::new(Rect::default()),
+        titles_area: Cell::new(Rect::default()),
         panel_current: Panel::Titles,
         show_mode: ShowMode::EntryList,
         mode: Mode::Normal,
-        command_buffer: String::new(),
+        command_buffer: Input::default(),
+        history: History::load(),
+        reverse_search: None,
+        suggestions: vec![],
+        suggestion_selected: None,
+        suggest_at: None,
+        completion: None,
         info_buffer: String::new(),
+        filter: Filter::default(),
+        opener: Opener::from_config(&Config::default()),
+        image_shown: false,
+        spoilers_revealed: vec![],
+        help_shown: false,
+        help_scroll: 0,
+        theme: Theme::dark(),
+        titles_width: 60,
+        show_titles: true,
+        config: Config::load(),
     };
+    app.filter = Filter::from_config(&app.config);
+    app.opener = Opener::from_config(&app.config);
+    app.load_ui();
+    match Theme::from_config(&app.config, ColorSupport::detect()) {
+        Ok(theme) => app.theme = theme,
+        Err(e) => app.info_buffer = e,
+    }
 
-    let (_sender, receiver) = init_events();
+    let (sender, receiver) = init_events();
 
     // Load popular titles
-    app.title_page += 1;
-    app.titles.append(&mut eksi::popular_titles(app.title_page));
+    let titles = app.fetch_titles(app.title_page);
+    app.append_titles(titles);
+
+    // eksi-cli <link or title>
+    if let Some(target) = env::args().nth(1) {
+        app.run_command(Command::Open(target));
+    }
     app.draw_ui(&mut term).expect("Something went wrong.");
 
     loop {
+        let event = match app.suggest_at {
+            // Wait for the typing to stop before asking for suggestions
+            Some(at) => {
+                let wait = SUGGEST_DELAY.checked_sub(at.elapsed()).unwrap_or_default();
+                match receiver.recv_timeout(wait) {
+                    Ok(event) => event,
+                    Err(_) => {
+                        app.request_suggestions(&sender);
+                        continue;
+                    }
+                }
+            },
+            None => receiver.recv().unwrap(),
+        };
+        let focused = app.entry_current;
+
+        if app.image_shown {
+            // Any key hides the image
+            app.image_shown = false;
+            let size = term.size().unwrap();
+            term.resize(size).unwrap();
+            app.draw_ui(&mut term).expect("Something went wrong.");
+            continue;
+        }
+
         match app.mode {
+            Mode::Normal if app.help_shown => {
+                match event {
+                    Event::Input(Key::Char('j')) | Event::Input(Key::Down) => {
+                        app.scroll_help(1);
+                    },
+                    Event::Input(Key::Char('k')) | Event::Input(Key::Up) => {
+                        app.scroll_help(-1);
+                    },
+                    Event::Input(_) => {
+                        // Any other key closes the help
+                        app.help_shown = false;
+                    },
+                    _ => {}
+                }
+            },
             Mode::Normal => {
-                match receiver.recv().unwrap() {
-                    Event::Input(Key::Char('q')) => {
+                let key = match event {
+                    Event::Input(key) => key,
+                    Event::Mouse(mouse) => {
+                        app.mouse(mouse);
+                        Key::Null
+                    },
+                    _ => Key::Null,
+                };
+
+                let action = keys::action(key, &app.key_context());
+                match action {
+                    Some(Action::Quit) => {
                         // Simply quit
                         break;
                     },
-                    Event::Input(Key::Down) | Event::Input(Key::Char('j')) => {
+                    Some(Action::Down) => {
                         match app.panel_current {
                             Panel::Titles => {
                                 // Select next title
                                 if app.title_selected >= app.titles.len() {
                                     // Load next titles
                                     app.title_page += 1;
-                                    app.titles.append(&mut eksi::popular_titles(app.title_page));
+                                    let titles = app.fetch_titles(app.title_page);
+                                    app.append_titles(titles);
                                 }
                                 app.title_selected += 1;
                             },
                             Panel::Entries => {
-                                // Select next entry (infinitely)
-                                app.entry_current += 1; // FIXME: check if we are at the end of that title
-
-                                if app.entry_current >= app.entries.len() {
-                                    app.entry_page += 1;
-                                    let mut title = app.title
-                                        .as_ref()
-                                        .expect("app.title is None");
-
-                                    // If the title is accessed trough Popular's
-                                    // it will have a popular_count, so we can
-                                    // safely get popular entries
-                                    let mut entries = if title.popular_count.is_some() {
-                                        title.entries(app.entry_page, app.popular)
-                                    } else {
-                                        title.entries(app.entry_page, false)
-                                    };
-
-                                    app.entries.append(&mut entries);
+                                if app.show_mode != ShowMode::EntryList && app.scroll_entry(1) {
+                                    // Scrolled inside a long entry
+                                } else if app.entry_current + 1 < app.entries.len() || app.load_more_entries() {
+                                    // Select next entry (infinitely)
+                                    app.entry_current += 1;
                                 }
                             },
                         }
                     },
-                    Event::Input(Key::Up) | Event::Input(Key::Char('k')) => {
+                    Some(Action::Up) => {
                         match app.panel_current {
                             Panel::Titles => {
                                 // Select prev title
                                 app.title_selected -= 1;
                             },
                             Panel::Entries => {
-                                // Select prev entry
-                                if app.entry_current > 0 {
+                                if app.show_mode != ShowMode::EntryList && app.scroll_entry(-1) {
+                                    // Scrolled inside a long entry
+                                } else if app.entry_current > 0 {
+                                    // Select prev entry
                                     app.entry_current -= 1;
+                                } else if app.entry_first_page > 0 {
+                                    // Load the previous page, after a :goto or :page
+                                    app.entry_first_page -= 1;
+                                    let entries = app.fetch_entries(app.entry_first_page).unwrap_or_default();
+                                    let count = app.prepend_entries(entries);
+                                    app.entry_current = count.saturating_sub(1);
                                 }
                             }
                         }
                     },
-                    Event::Input(Key::Right) | Event::Input(Key::Char('\n'))
-                        | Event::Input(Key::Char('l')) => {
+                    Some(Action::Select) if app.panel_current == Panel::Entries
+                        && app.show_mode != ShowMode::EntryList && app.ref_current.is_some() => {
+                        // Open the focused reference
+                        app.open_reference();
+                    },
+                    Some(Action::Select) => {
                         match app.panel_current {
                             Panel::Titles => {
-                                // Clean up
-                                // Change the title to selected one
-                                let entries = app.titles[app.title_selected]
-                                    .entries(app.entry_page, app.popular);
-
-                                app.update_entries(entries);
-
-                                app.title_current = app.title_selected;
-                                app.title = Some(app.titles[app.title_selected].clone());
-
-                                app.focus_entries();
+                                app.open_selected_title();
                             },
-                            Panel::Entries => {
-                                // Go into single entry mode
-                                app.show_mode = ShowMode::SingleEntry;
+                            Panel::Entries => match app.show_mode {
+                                ShowMode::Debe => {
+                                    // Open the title of the debe entry
+                                    let title = app.entries.get(app.entry_current).and_then(|x| x.title.clone());
+                                    if let Some(title) = title {
+                                        app.entry_filter = EntryFilter::All;
+                                        let entries = title.entries(0, &app.entry_filter);
+                                        app.open_title(title, entries);
+                                    }
+                                },
+                                _ => {
+                                    // Go into single entry mode
+                                    app.show_mode = ShowMode::SingleEntry;
+                                }
                             }
                         }
                     },
-                    Event::Input(Key::Left) | Event::Input(Key::Esc)
-                        | Event::Input(Key::Char('h')) => {
+                    Some(Action::Back) => {
                         match app.panel_current {
                             Panel::Titles => {},
                             Panel::Entries => match app.show_mode {
                                 ShowMode::SingleEntry => {
                                     app.show_mode = ShowMode::EntryList;
                                 },
-                                ShowMode::EntryList => {
+                                ShowMode::EntryList | ShowMode::Debe => {
                                     app.focus_titles();
                                 }
                             }
                         }
                     },
-                    Event::Input(Key::Char('/')) => {
+                    Some(Action::Search) => {
                         // Open search
-                        app.mode = Mode::CommandInput;
-                        app.command_buffer = "/".to_string();
+                        app.open_command_input("/");
+                    },
+                    Some(Action::NextChannel) => {
+                        // Next channel
+                        app.cycle_channel(true);
+                    },
+                    Some(Action::PrevChannel) => {
+                        // Previous channel
+                        app.cycle_channel(false);
+                    },
+                    Some(Action::ToggleAuthor) => {
+                        // Narrow the title to the author of the entry, or widen it back
+                        let author = app.entries.get(app.entry_current).map(|x| x.author.name.clone());
+                        let narrowed = match app.entry_filter {
+                            EntryFilter::Author(_) => true,
+                            _ => false,
+                        };
+
+                        if narrowed {
+                            let filter = app.title.as_ref().map_or(EntryFilter::All, |x| app.default_entry_filter(x));
+                            app.set_entry_filter(filter);
+                        } else if let Some(author) = author {
+                            app.set_entry_filter(EntryFilter::Author(author));
+                        }
+                    },
+                    Some(Action::CycleSort) => {
+                        // Cycle sort modes of the title
+                        app.cycle_entry_filter();
+                    },
+                    Some(Action::Debe) => {
+                        // Open debe
+                        app.switch_channel(Channel::Debe);
+                    },
+                    Some(Action::OpenLink) => {
+                        // Open a link of the entry
+                        app.pick_link("link");
+                    },
+                    Some(Action::YankLink) => {
+                        // Copy a link of the entry
+                        app.pick_link("yank");
+                    },
+                    Some(Action::NextReference) | Some(Action::PrevReference) => {
+                        // Focus the next or the previous reference of the entry
+                        app.cycle_refs(action == Some(Action::NextReference));
+                    },
+                    Some(Action::FilterTitles) => {
+                        // Fuzzy filter over the titles
+                        app.open_title_filter();
+                    },
+                    Some(Action::Find) => {
+                        // Find in the loaded entries
+                        app.open_command_input(":find ");
+                    },
+                    Some(Action::FindNext) => {
+                        // Next entry with a match
+                        app.find_next(true, false);
+                    },
+                    Some(Action::FindPrev) => {
+                        // Previous entry with a match
+                        app.find_next(false, false);
+                    },
+                    Some(Action::ToggleSpoilers) => {
+                        // Reveal or collapse the spoilers of the entry
+                        app.toggle_spoilers();
+                    },
+                    Some(Action::LineDown) | Some(Action::LineUp)
+                        | Some(Action::PageDown) | Some(Action::PageUp) => {
+                        // Scroll the entries by a line or a page, a single
+                        // entry is scrolled by half a page
+                        let page = match app.show_mode {
+                            ShowMode::EntryList => app.entries_area.get().height as isize,
+                            _ => app.entries_area.get().height as isize / 2,
+                        };
+                        let rows = match action {
+                            Some(Action::LineDown) => 1,
+                            Some(Action::LineUp) => -1,
+                            Some(Action::PageDown) => page,
+                            _ => -page,
+                        };
+
+                        if app.show_mode == ShowMode::EntryList {
+                            app.scroll_entries(rows);
+                        } else {
+                            app.scroll_entry(rows);
+                        }
+                    },
+                    Some(Action::Top) | Some(Action::Bottom) => {
+                        // Go to the top or the bottom of the entry, or the
+                        // first or the last loaded entry
+                        let bottom = action == Some(Action::Bottom);
+                        match (&app.show_mode, bottom) {
+                            (&ShowMode::EntryList, false) => app.entry_current = 0,
+                            (&ShowMode::EntryList, true) => app.entry_current = app.entries.len().saturating_sub(1),
+                            (_, false) => app.entry_line = 0,
+                            (_, true) => {
+                                app.scroll_entry(isize::max_value());
+                            }
+                        }
                     },
-                    Event::Input(Key::Char('\t')) => {
+                    Some(Action::NarrowTitles) => {
+                        // Narrow the titles pane
+                        app.resize_titles(-(RESIZE_STEP as i32));
+                    },
+                    Some(Action::WidenTitles) => {
+                        // Widen the titles pane
+                        app.resize_titles(RESIZE_STEP as i32);
+                    },
+                    Some(Action::ToggleTitles) => {
+                        // Show or hide the titles pane
+                        app.toggle_titles();
+                    },
+                    Some(Action::DetailedSearch) => {
+                        // Open detailed search
+                        app.mode = Mode::SearchForm;
+                    },
+                    Some(Action::Command) => {
+                        // Open command input
+                        app.open_command_input(":");
+                    },
+                    Some(Action::SwitchPanel) => {
                         // (Keys::Tab) Cycle trough panels
                         app.panel_current = match app.panel_current {
                             Panel::Titles => Panel::Entries,
                             Panel::Entries => Panel::Titles
                         };
                     },
-                    _ => {
+                    Some(Action::QuickLink) => {
+                        // Open a link of the start page
+                        if let Key::Char(digit) = key {
+                            app.open_quick_link(digit.to_digit(10).unwrap_or(0) as usize);
+                        }
+                    },
+                    Some(Action::Help) => {
+                        app.help_shown = true;
+                        app.help_scroll = 0;
+                    },
+                    Some(Action::Complete) | Some(Action::HistorySearch) | Some(Action::ChangeField) => {
+                        // Only bound in the modes that take text
+                    },
+                    None => {
                         // FIXME: add not defined warning
                     }
                 }
             },
             Mode::CommandInput => {
-                match receiver.recv().unwrap() {
-                    Event::Input(Key::Esc) => {
-                        // Quit CommandInput
-                        app.mode = Mode::Normal;
-                        app.command_buffer.clear();
-                    },
-                    Event::Input(Key::Char('\n')) => {
-                        // (Key::Enter) Execute command
-                        app.execute_command();
-                    },
-                    Event::Input(Key::Char(ch)) => {
-                        // Push chars to command buffer
-                        app.command_buffer.push(ch);
+                match event {
+                    Event::Input(key) => if app.reverse_search.is_some() {
+                        app.reverse_search_key(key);
+                    } else {
+                        app.command_key(key);
                     },
-                    Event::Input(Key::Backspace) => {
-                        // Pop one char from command buffer
-                        app.command_buffer.pop();
+                    Event::Suggestions(query, suggestions) => {
+                        app.show_suggestions(query, suggestions);
                     },
-                    _ => {}
+                    Event::Mouse(_) => {}
+                }
+            }
+            Mode::SearchForm => {
+                if let Event::Input(key) = event {
+                    match keys::action(key, &Context::input(Scope::SearchForm)) {
+                        Some(Action::Back) => {
+                            app.mode = Mode::Normal;
+                        },
+                        Some(Action::Select) => {
+                            app.submit_search_form();
+                        },
+                        Some(Action::Down) => {
+                            app.search_form.next_field();
+                        },
+                        Some(Action::Up) => {
+                            app.search_form.prev_field();
+                        },
+                        _ => {
+                            // Space and the arrows change the focused field
+                            app.search_form.handle(key);
+                        },
+                    }
                 }
             }
+            Mode::TitleFilter => {
+                if let Event::Input(key) = event {
+                    app.title_filter_key(key);
+                }
+            }
+        }
+
+
+        if app.quit {
+            break;
+        }
+
+        if app.entry_current != focused {
+            app.entry_line = 0;
+            app.ref_current = None;
+            app.reveal_current();
         }
 
+        if app.image_shown {
+            // Keep the image on the screen until the next key
+            continue;
+        }
 
         app.update_size(&mut term);
         app.draw_ui(&mut term).expect("Something went wrong.");
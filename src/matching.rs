@@ -0,0 +1,230 @@
+//! Text matching that ignores the case and the Turkish letters, so `ışık`,
+//! `IŞIK` and `isik` are the same.
+
+use std::ops::Range;
+
+/// Score of each matched char of a fuzzy match
+const SCORE_MATCH: i32 = 16;
+/// Extra score of a match at the start of a word
+const BONUS_WORD_START: i32 = 8;
+/// Extra score of a match right after the previous one
+const BONUS_CONSECUTIVE: i32 = 8;
+/// Penalty of a gap between the matches, and of each char in it
+const PENALTY_GAP_START: i32 = 5;
+const PENALTY_GAP: i32 = 1;
+
+/// Folds given char to the lowercase ASCII letter it's matched as, the
+/// chars other than the Turkish letters are only lowercased
+pub fn fold(ch: char) -> char {
+    let lower = match ch {
+        'I' | 'İ' => 'i',
+        _ => ch.to_lowercase().next().unwrap_or(ch),
+    };
+
+    match lower {
+        'ı' | 'î' => 'i',
+        'ş' => 's',
+        'ğ' => 'g',
+        'ü' | 'û' => 'u',
+        'ö' => 'o',
+        'ç' => 'c',
+        'â' => 'a',
+        _ => lower,
+    }
+}
+
+/// Returns the byte ranges of the matches of `query` in `text`, the matches
+/// don't overlap
+pub fn find_all(text: &str, query: &str) -> Vec<Range<usize>> {
+    let query: Vec<char> = query.chars().map(fold).collect();
+    let chars: Vec<(usize, char)> = text.char_indices().map(|(i, x)| (i, fold(x))).collect();
+    let mut matches = vec![];
+    if query.is_empty() {
+        return matches;
+    }
+
+    let mut i = 0;
+    while i + query.len() <= chars.len() {
+        let found = chars[i..i + query.len()].iter().zip(&query).all(|(a, b)| a.1 == *b);
+        if !found {
+            i += 1;
+            continue;
+        }
+
+        let end = chars.get(i + query.len()).map_or(text.len(), |x| x.0);
+        matches.push(chars[i].0..end);
+        i += query.len();
+    }
+
+    matches
+}
+
+/// Returns the nearest index after `current` that `matches`, or the nearest
+/// one before it if not `forward`. `current` itself is checked too if
+/// `inclusive`, only the indexes below `len` are checked.
+pub fn next_index<F>(current: usize, len: usize, forward: bool, inclusive: bool, mut matches: F) -> Option<usize>
+    where F: FnMut(usize) -> bool
+{
+    if forward {
+        let start = if inclusive { current } else { current + 1 };
+        (start..len).find(|&i| matches(i))
+    } else {
+        let end = if inclusive { current + 1 } else { current };
+        (0..end.min(len)).rev().find(|&i| matches(i))
+    }
+}
+
+/// A fuzzy match of a query in a text
+#[derive(Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Byte indexes of the matched chars in the text
+    pub positions: Vec<usize>,
+}
+
+/// Matches the chars of `query` in `text` in order, like fzf. Matches at
+/// the starts of the words and consecutive ones score higher, the gaps
+/// between them score lower. The spaces in the query are ignored, an empty
+/// query matches everything.
+pub fn fuzzy(text: &str, query: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().filter(|x| !x.is_whitespace()).map(fold).collect();
+    let chars: Vec<(usize, char)> = text.char_indices().map(|(i, x)| (i, fold(x))).collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch { score: 0, positions: vec![] });
+    }
+
+    // The first place where the whole query matches ends at `end`
+    let mut matched = 0;
+    let end = chars.iter().position(|x| {
+        if x.1 == query[matched] {
+            matched += 1;
+        }
+        matched == query.len()
+    })?;
+
+    // The shortest match that ends there starts at `start`
+    let mut left = query.len();
+    let start = (0..=end).rev().find(|&i| {
+        if chars[i].1 == query[left - 1] {
+            left -= 1;
+        }
+        left == 0
+    })?;
+
+    let mut score = 0;
+    let mut positions = vec![];
+    let mut last: Option<usize> = None;
+    let mut next = 0;
+    for i in start..=end {
+        if next == query.len() || chars[i].1 != query[next] {
+            continue;
+        }
+
+        score += SCORE_MATCH;
+        if i == 0 || !chars[i - 1].1.is_alphanumeric() {
+            score += BONUS_WORD_START;
+        }
+        match last {
+            Some(last) if last + 1 == i => score += BONUS_CONSECUTIVE,
+            Some(last) => score -= PENALTY_GAP_START + PENALTY_GAP * (i - last - 2) as i32,
+            None => {}
+        }
+
+        positions.push(chars[i].0);
+        last = Some(i);
+        next += 1;
+    }
+
+    Some(FuzzyMatch {
+        score: score,
+        positions: positions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds() {
+        let cases: &[(&str, &str, &[(usize, usize)])] = &[
+            ("rust", "rust", &[(0, 4)]),
+            ("Rust rust RUST", "rust", &[(0, 4), (5, 9), (10, 14)]),
+            ("ışık", "isik", &[(0, 7)]),
+            ("IŞIK", "ışık", &[(0, 5)]),
+            ("İstanbul", "istanbul", &[(0, 9)]),
+            ("kâğıt", "kagit", &[(0, 8)]),
+            ("aaaa", "aa", &[(0, 2), (2, 4)]),
+            ("çay, şeker", "seker", &[(6, 12)]),
+            ("rust", "", &[]),
+            ("rust", "go", &[]),
+            ("ab", "abc", &[]),
+        ];
+
+        for &(text, query, expected) in cases {
+            let expected: Vec<Range<usize>> = expected.iter().map(|&(a, b)| a..b).collect();
+            assert_eq!(find_all(text, query), expected, "{:?} in {:?}", query, text);
+        }
+    }
+
+    #[test]
+    fn finds_next_index() {
+        let matched = [false, true, false, true, true];
+        let cases = &[
+            (0, true, false, Some(1)),
+            (1, true, false, Some(3)),
+            (1, true, true, Some(1)),
+            (4, true, false, None),
+            (4, false, false, Some(3)),
+            (4, false, true, Some(4)),
+            (1, false, false, None),
+            (0, false, true, None),
+            // The current index may be past the end after a refilter
+            (7, false, false, Some(4)),
+            (7, true, true, None),
+        ];
+
+        for &(current, forward, inclusive, expected) in cases {
+            let found = next_index(current, matched.len(), forward, inclusive, |i| matched[i]);
+            assert_eq!(found, expected, "from {} forward: {} inclusive: {}", current, forward, inclusive);
+        }
+    }
+
+    #[test]
+    fn fuzzy_matches() {
+        let cases: &[(&str, &str, Option<&[usize]>)] = &[
+            ("rust", "rust", Some(&[0, 1, 2, 3])),
+            ("rust programlama dili", "rpd", Some(&[0, 5, 17])),
+            ("ışık", "isik", Some(&[0, 2, 4, 6])),
+            ("Şiir", "si", Some(&[0, 2])),
+            ("İzmir", "izm", Some(&[0, 2, 3])),
+            // The shortest match is picked
+            ("aab", "ab", Some(&[1, 2])),
+            ("rust", "rs t", Some(&[0, 2, 3])),
+            ("rust", "", Some(&[])),
+            ("rust", "tsur", None),
+            ("rust", "rusty", None),
+        ];
+
+        for &(text, query, expected) in cases {
+            let positions = fuzzy(text, query).map(|x| x.positions);
+            assert_eq!(positions, expected.map(|x| x.to_vec()), "{:?} in {:?}", query, text);
+        }
+    }
+
+    #[test]
+    fn fuzzy_scores() {
+        // The first text of each pair scores higher
+        let cases = &[
+            ("rust", "r u s t", "rust"),
+            ("rust programlama", "rust ve programlama", "rp"),
+            ("bugün hava", "bugünhava", "bh"),
+            ("kitap", "kral ilk tur", "kit"),
+        ];
+
+        for &(better, worse, query) in cases {
+            let score = |text| fuzzy(text, query).unwrap().score;
+            assert!(score(better) > score(worse), "{:?} for {:?} over {:?}", query, better, worse);
+        }
+    }
+}
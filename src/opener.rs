@@ -0,0 +1,144 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use eksicli::config::Config;
+
+const SECTION: &str = "open";
+const IMAGE_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png", ".gif", ".webp", ".bmp"];
+
+/// External commands for the links in the entries.
+///
+/// Commands are read from the `[open]` section of the config file, `{}` is
+/// replaced with the link. Without `{}` the link is given as the last
+/// argument.
+///
+/// ```text
+/// [open]
+/// command = firefox --new-tab {}
+/// image = curl -s {} | chafa -
+/// ```
+#[derive(Debug)]
+pub struct Opener {
+    /// Opens the links in the background, `xdg-open` by default
+    pub command: String,
+    /// Prints the images to the terminal, images are opened with `command`
+    /// if it's not set
+    pub image: Option<String>,
+}
+
+impl Opener {
+    pub fn from_config(config: &Config) -> Opener {
+        Opener {
+            command: config.get(SECTION, "command").unwrap_or("xdg-open").to_string(),
+            image: config.get(SECTION, "image").map(|x| x.to_string()),
+        }
+    }
+
+    /// Opens given link in the background
+    pub fn open(&self, url: &str) -> io::Result<()> {
+        let mut child = shell(&self.command, url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // Reap the child without blocking the ui
+        thread::spawn(move || child.wait());
+
+        Ok(())
+    }
+
+    /// Prints given image with the image viewer command, returns false if
+    /// there is no image viewer
+    pub fn show_image(&self, url: &str) -> io::Result<bool> {
+        let command = match self.image {
+            Some(ref command) => command,
+            None => return Ok(false),
+        };
+
+        let output = shell(command, url).stdin(Stdio::null()).output()?;
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::new(io::ErrorKind::Other, error.trim().to_string()));
+        }
+
+        // The terminal is in raw mode, new lines don't return the carriage
+        let text = String::from_utf8_lossy(&output.stdout).replace("\n", "\r\n");
+        let mut stdout = io::stdout();
+        write!(stdout, "\x1b[2J\x1b[H{}", text)?;
+        stdout.flush()?;
+
+        Ok(true)
+    }
+}
+
+/// Runs `command` with `sh`, the link is passed as `$1` so it is never
+/// interpreted by the shell
+fn shell(command: &str, url: &str) -> Command {
+    let script = if command.contains("{}") {
+        command.replace("{}", "\"$1\"")
+    } else {
+        format!("{} \"$1\"", command)
+    };
+
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(script).arg("sh").arg(url);
+    shell
+}
+
+/// Returns true if given link looks like an image
+pub fn is_image(url: &str) -> bool {
+    let path = url.split(|x| x == '?' || x == '#').next().unwrap_or("").to_lowercase();
+    IMAGE_EXTENSIONS.iter().any(|x| path.ends_with(x))
+}
+
+/// Copies given text to the clipboard of the terminal with the OSC 52
+/// escape sequence, it works over ssh too
+pub fn yank(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &x)| n | ((x as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64() {
+        // RFC 4648, section 10
+        let cases = &[
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+            ("ışık", "xLHFn8Sxaw=="),
+        ];
+
+        for &(text, expected) in cases {
+            assert_eq!(base64(text.as_bytes()), expected, "base64 of {:?}", text);
+        }
+    }
+}
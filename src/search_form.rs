@@ -0,0 +1,170 @@
+use termion::event::Key;
+
+use tui::Terminal;
+use tui::backend::MouseBackend;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::widgets::{Paragraph, Widget};
+
+use eksicli::endpoints::search::{SearchQuery, SortOrder};
+
+use command;
+use input::{Input, InputLine};
+use keys::{self, Context, Scope};
+use theme::Theme;
+
+const LABELS: &[&str] = &[
+    "kelimeler:   ",
+    "yazar:       ",
+    "tarihinden:  ",
+    "tarihine:    ",
+    "sadece baslikta: ",
+    "siralama:    ",
+];
+const TITLE_ONLY: usize = 4;
+const SORT: usize = 5;
+
+/// Detailed search form
+#[derive(Debug, Default)]
+pub struct SearchForm {
+    keywords: Input,
+    author: Input,
+    from: Input,
+    to: Input,
+    title_only: bool,
+    sort: SortOrder,
+    /// Index of the focused field
+    field: usize,
+}
+
+impl SearchForm {
+    pub fn next_field(&mut self) {
+        self.field = (self.field + 1) % LABELS.len();
+    }
+
+    pub fn prev_field(&mut self) {
+        self.field = (self.field + LABELS.len() - 1) % LABELS.len();
+    }
+
+    fn input(&self, field: usize) -> Option<&Input> {
+        match field {
+            0 => Some(&self.keywords),
+            1 => Some(&self.author),
+            2 => Some(&self.from),
+            3 => Some(&self.to),
+            _ => None,
+        }
+    }
+
+    fn input_mut(&mut self, field: usize) -> Option<&mut Input> {
+        match field {
+            0 => Some(&mut self.keywords),
+            1 => Some(&mut self.author),
+            2 => Some(&mut self.from),
+            3 => Some(&mut self.to),
+            _ => None,
+        }
+    }
+
+    /// Edits the focused field, space toggles the checkbox and cycles the
+    /// sort order
+    pub fn handle(&mut self, key: Key) -> bool {
+        let field = self.field;
+        match (field, key) {
+            (TITLE_ONLY, Key::Char(' ')) => self.title_only = !self.title_only,
+            (SORT, Key::Char(' ')) | (SORT, Key::Right) => {
+                self.sort = match self.sort {
+                    SortOrder::Date => SortOrder::Alphabetical,
+                    SortOrder::Alphabetical => SortOrder::Count,
+                    SortOrder::Count => SortOrder::Date,
+                }
+            }
+            (SORT, Key::Left) => {
+                self.sort = match self.sort {
+                    SortOrder::Date => SortOrder::Count,
+                    SortOrder::Alphabetical => SortOrder::Date,
+                    SortOrder::Count => SortOrder::Alphabetical,
+                }
+            }
+            _ => return self.input_mut(field).map_or(false, |x| x.handle(key)),
+        }
+
+        true
+    }
+
+    /// Returns the query, or an error message for invalid fields
+    pub fn query(&self) -> Result<SearchQuery, String> {
+        let keywords = self.keywords.as_str().trim();
+        let author = self.author.as_str().trim().trim_left_matches('@');
+        if keywords.is_empty() && author.is_empty() {
+            return Err("Enter some keywords or an author.".to_string());
+        }
+
+        let mut query = SearchQuery::new(keywords)
+            .author(author)
+            .title_only(self.title_only)
+            .sort(self.sort);
+
+        let parse_date = |date: &str| {
+            command::parse_day(date).ok_or_else(|| format!("Invalid date: {} (YYYY-MM-DD)", date))
+        };
+        let from = self.from.as_str().trim();
+        if !from.is_empty() {
+            query = query.from(&parse_date(from)?);
+        }
+        let to = self.to.as_str().trim();
+        if !to.is_empty() {
+            query = query.to(&parse_date(to)?);
+        }
+
+        Ok(query)
+    }
+
+    pub fn draw(&self, t: &mut Terminal<MouseBackend>, area: &Rect, theme: &Theme) {
+        let normal_style = Style::default();
+        let focus_style = theme.selection;
+
+        for (i, label) in LABELS.iter().enumerate() {
+            let y = area.y + 2 * i as u16;
+            if y >= area.y + area.height {
+                break;
+            }
+
+            let rect = Rect { x: area.x, y: y, width: area.width, height: 1 };
+            let style = if i == self.field { focus_style } else { normal_style };
+
+            match self.input(i) {
+                Some(input) if i == self.field => {
+                    InputLine::new(input)
+                        .prefix(label)
+                        .style(style)
+                        .render(t, &rect);
+                }
+                _ => {
+                    let value = match i {
+                        TITLE_ONLY => (if self.title_only { "[x]" } else { "[ ]" }).to_string(),
+                        SORT => format!("< {} >", match self.sort {
+                            SortOrder::Date => "tarih",
+                            SortOrder::Alphabetical => "alfabetik",
+                            SortOrder::Count => "entry sayisi",
+                        }),
+                        _ => self.input(i).map_or(String::new(), |x| x.as_str().to_string()),
+                    };
+
+                    Paragraph::default()
+                        .style(style)
+                        .text(&(label.to_string() + &value))
+                        .render(t, &rect);
+                }
+            }
+        }
+
+        let help_y = area.y + 2 * LABELS.len() as u16;
+        if help_y < area.y + area.height {
+            Paragraph::default()
+                .style(theme.muted.modifier(Modifier::Italic))
+                .text(&keys::footer(&Context::input(Scope::SearchForm)))
+                .render(t, &Rect { x: area.x, y: help_y, width: area.width, height: 1 });
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Url helpers that follow the rules of the site: title slugs and
+//! percent-encoding of query parameters.
+
+/// Lowercases given text with the Turkish rules, `I` becomes `ı` and `İ`
+/// becomes `i`
+pub fn lowercase(text: &str) -> String {
+    text.chars()
+        .flat_map(|x| match x {
+            'I' => vec!['ı'],
+            'İ' => vec!['i'],
+            _ => x.to_lowercase().collect(),
+        })
+        .collect()
+}
+
+/// Maps the letters with diacritics to their ASCII counterparts, returns
+/// `None` for the other characters
+fn transliterate(ch: char) -> Option<&'static str> {
+    let ascii = match ch {
+        'ı' | 'î' | 'ï' | 'í' | 'ì' => "i",
+        'ğ' => "g",
+        'ü' | 'û' | 'ú' | 'ù' => "u",
+        'ş' | 'ś' => "s",
+        'ö' | 'ô' | 'ó' | 'ò' | 'õ' | 'ø' => "o",
+        'ç' | 'ć' => "c",
+        'â' | 'ä' | 'á' | 'à' | 'ã' | 'å' => "a",
+        'é' | 'è' | 'ê' | 'ë' => "e",
+        'ñ' => "n",
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        _ => return None,
+    };
+
+    Some(ascii)
+}
+
+/// Returns the slug of given title, like `turkiyenin-en-iyi-filmi` for
+/// `Türkiye'nin En İyi Filmi`. Apostrophes are dropped, every other run of
+/// non-alphanumeric characters becomes a single `-`. Letters that can't be
+/// transliterated are kept as is, `Title::to_url` encodes them.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    let mut separator = false;
+
+    for ch in lowercase(title).chars() {
+        if ch == '\'' || ch == '’' || is_combining(ch) {
+            continue;
+        }
+
+        let ascii = transliterate(ch);
+        if ascii.is_none() && !ch.is_alphanumeric() {
+            // Leading and trailing separators are dropped
+            separator = !slug.is_empty();
+            continue;
+        }
+
+        if separator {
+            slug.push('-');
+            separator = false;
+        }
+
+        match ascii {
+            Some(ascii) => slug.push_str(ascii),
+            None => slug.push(ch),
+        }
+    }
+
+    slug
+}
+
+/// Combining diacritical marks, like the dot of a decomposed `i̇`
+fn is_combining(ch: char) -> bool {
+    match ch as u32 {
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F => true,
+        _ => false,
+    }
+}
+
+/// Percent-encodes given text to be used in a query parameter or a path
+/// segment, only the unreserved characters are kept
+pub fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Decodes a percent-encoded query parameter or path segment, `+` is a space.
+/// Invalid escapes are kept as they are.
+pub fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = |x: u8| (x as char).to_digit(16);
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push((high * 16 + low) as u8);
+                        i += 3;
+                        continue;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs() {
+        let cases = &[
+            ("rust", "rust"),
+            ("Rust Programlama Dili", "rust-programlama-dili"),
+            ("IŞIK", "isik"),
+            ("İstanbul", "istanbul"),
+            ("i̇stanbul", "istanbul"),
+            ("ığüşöç ĞÜŞÖÇ", "igusoc-gusoc"),
+            ("türkiye'nin en iyi filmi", "turkiyenin-en-iyi-filmi"),
+            ("türkiye’nin", "turkiyenin"),
+            ("kâğıt", "kagit"),
+            ("hâlâ", "hala"),
+            ("mîmar", "mimar"),
+            ("Café Crème", "cafe-creme"),
+            ("c++", "c"),
+            ("c#", "c"),
+            ("tom & jerry", "tom-jerry"),
+            ("ne?", "ne"),
+            ("1/2", "1-2"),
+            ("node.js", "node-js"),
+            ("  boşluklu   başlık  ", "bosluklu-baslik"),
+            ("--- spoiler ---", "spoiler"),
+            ("#100yıl", "100yil"),
+            ("emoji 🎉 başlık", "emoji-baslik"),
+            ("🎉", ""),
+            ("東京", "東京"),
+            ("москва 2018", "москва-2018"),
+            ("straße", "strasse"),
+        ];
+
+        for &(title, slug) in cases {
+            assert_eq!(slugify(title), slug, "slug of {:?}", title);
+        }
+    }
+
+    #[test]
+    fn lowercases() {
+        let cases = &[
+            ("IRMAK", "ırmak"),
+            ("İZMİR", "izmir"),
+            ("Iİ", "ıi"),
+            ("ŞÇĞÜÖ", "şçğüö"),
+        ];
+
+        for &(text, lower) in cases {
+            assert_eq!(lowercase(text), lower, "lowercase of {:?}", text);
+        }
+    }
+
+    #[test]
+    fn encodes() {
+        let cases = &[
+            ("rust", "rust"),
+            ("a b", "a%20b"),
+            ("ş", "%C5%9F"),
+            ("görsel", "g%C3%B6rsel"),
+            ("http://", "http%3A%2F%2F"),
+            ("a&b=c?d#e", "a%26b%3Dc%3Fd%23e"),
+            ("c++", "c%2B%2B"),
+            ("100%", "100%25"),
+            ("node.js_~-", "node.js_~-"),
+            ("🎉", "%F0%9F%8E%89"),
+        ];
+
+        for &(text, encoded) in cases {
+            assert_eq!(encode(text), encoded, "encoding of {:?}", text);
+            assert_eq!(decode(encoded), text, "decoding of {:?}", encoded);
+        }
+    }
+
+    #[test]
+    fn decodes() {
+        let cases = &[
+            ("a+b", "a b"),
+            ("%c5%9f", "ş"),
+            ("100%", "100%"),
+            ("%zz", "%zz"),
+            ("%C5", "\u{FFFD}"),
+        ];
+
+        for &(text, decoded) in cases {
+            assert_eq!(decode(text), decoded, "decoding of {:?}", text);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Style;
+use tui::widgets::Widget;
+
+use text;
+
+/// A single line made of differently styled parts
+pub struct Spans<'a> {
+    spans: &'a [(String, Style)],
+    right_aligned: bool,
+}
+
+impl<'a> Spans<'a> {
+    pub fn new(spans: &'a [(String, Style)]) -> Spans<'a> {
+        Spans {
+            spans: spans,
+            right_aligned: false,
+        }
+    }
+
+    pub fn right_aligned(&mut self, right_aligned: bool) -> &mut Spans<'a> {
+        self.right_aligned = right_aligned;
+        self
+    }
+}
+
+impl<'a> Widget for Spans<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+
+        let width = area.width as usize;
+        let total: usize = self.spans.iter().map(|x| text::width(&x.0)).sum();
+        let mut x = if self.right_aligned && total < width {
+            (width - total) as u16
+        } else {
+            0
+        };
+
+        for &(ref span, style) in self.spans {
+            let left = width.saturating_sub(x as usize);
+            if left == 0 {
+                break;
+            }
+
+            let span = text::truncate(span, left);
+            buf.set_stringn(area.x + x, area.y, &span, left, &style);
+            x += text::width(&span) as u16;
+        }
+    }
+}
+
+/// Splits the `line` range of given text into spans. The parts inside the
+/// marks get the styles of the marks, the rest gets `style`. The marks are
+/// in order and don't overlap.
+pub fn split(text: &str, line: Range<usize>, marks: &[(Range<usize>, Style)], style: Style) -> Vec<(String, Style)> {
+    let mut spans = vec![];
+    let mut offset = line.start;
+    for &(ref range, mark) in marks {
+        let start = range.start.max(offset);
+        let end = range.end.min(line.end);
+        if start >= end {
+            continue;
+        }
+
+        if offset < start {
+            spans.push((text[offset..start].to_string(), style));
+        }
+        spans.push((text[start..end].to_string(), mark));
+        offset = end;
+    }
+    if offset < line.end {
+        spans.push((text[offset..line.end].to_string(), style));
+    }
+
+    spans
+}
+
+/// Puts the `top` marks over `marks`, the parts of `marks` under them are
+/// dropped. Both lists are in order and don't overlap in themselves.
+pub fn overlay(marks: Vec<(Range<usize>, Style)>, top: Vec<(Range<usize>, Style)>) -> Vec<(Range<usize>, Style)> {
+    let mut merged = vec![];
+    for (range, style) in marks {
+        let mut start = range.start;
+        for &(ref over, _) in &top {
+            if over.end <= start || over.start >= range.end {
+                continue;
+            }
+
+            if start < over.start {
+                merged.push((start..over.start, style));
+            }
+            start = over.end;
+        }
+        if start < range.end {
+            merged.push((start..range.end, style));
+        }
+    }
+
+    merged.extend(top);
+    merged.sort_by_key(|x| x.0.start);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Color;
+
+    fn marks(marks: &[(usize, usize, Color)]) -> Vec<(Range<usize>, Style)> {
+        marks.iter().map(|&(a, b, color)| (a..b, Style::default().fg(color))).collect()
+    }
+
+    #[test]
+    fn overlays() {
+        let cases: &[(&[(usize, usize, Color)], &[(usize, usize, Color)], &[(usize, usize, Color)])] = &[
+            (&[(0, 10, Color::Red)], &[], &[(0, 10, Color::Red)]),
+            (&[], &[(2, 4, Color::Blue)], &[(2, 4, Color::Blue)]),
+            // The parts under the top marks are dropped
+            (
+                &[(0, 10, Color::Red)],
+                &[(2, 4, Color::Blue), (6, 8, Color::Green)],
+                &[(0, 2, Color::Red), (2, 4, Color::Blue), (4, 6, Color::Red), (6, 8, Color::Green), (8, 10, Color::Red)],
+            ),
+            (&[(2, 4, Color::Red)], &[(0, 10, Color::Blue)], &[(0, 10, Color::Blue)]),
+            (
+                &[(0, 4, Color::Red), (6, 10, Color::Yellow)],
+                &[(3, 7, Color::Blue)],
+                &[(0, 3, Color::Red), (3, 7, Color::Blue), (7, 10, Color::Yellow)],
+            ),
+            (&[(0, 2, Color::Red)], &[(2, 4, Color::Blue)], &[(0, 2, Color::Red), (2, 4, Color::Blue)]),
+        ];
+
+        for &(under, top, expected) in cases {
+            assert_eq!(overlay(marks(under), marks(top)), marks(expected), "{:?} under {:?}", under, top);
+        }
+    }
+
+    #[test]
+    fn splits() {
+        let style = Style::default();
+        let red = Style::default().fg(Color::Red);
+        let text = "merhaba dünya\nikinci";
+        let cases: &[(Range<usize>, &[(usize, usize, Color)], &[(&str, bool)])] = &[
+            (0..14, &[], &[("merhaba dünya", false)]),
+            (0..14, &[(0, 7, Color::Red)], &[("merhaba", true), (" dünya", false)]),
+            (0..14, &[(8, 14, Color::Red)], &[("merhaba ", false), ("dünya", true)]),
+            // The marks are cut at the ends of the line
+            (15..21, &[(8, 18, Color::Red)], &[("iki", true), ("nci", false)]),
+            (15..21, &[(0, 7, Color::Red)], &[("ikinci", false)]),
+        ];
+
+        for &(ref line, marked, expected) in cases {
+            let spans = split(text, line.clone(), &marks(marked), style);
+            let expected: Vec<(String, Style)> = expected.iter()
+                .map(|&(x, mark)| (x.to_string(), if mark { red } else { style }))
+                .collect();
+            assert_eq!(spans, expected, "{:?} of {:?}", marked, line);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const ELLIPSIS: char = '…';
+
+/// Returns the count of the terminal columns that given text takes. Wide
+/// chars take two columns, combining marks take none.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+pub fn char_width(ch: char) -> usize {
+    UnicodeWidthChar::width(ch).unwrap_or(0)
+}
+
+/// Cuts given text to fit into `width` columns, the cut is marked with an
+/// ellipsis
+pub fn truncate(text: &str, width: usize) -> String {
+    if self::width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let w = char_width(ch);
+        if used + w > width - 1 {
+            break;
+        }
+        truncated.push(ch);
+        used += w;
+    }
+    truncated.push(ELLIPSIS);
+
+    truncated
+}
+
+/// Pads given text from the left to align it to the right of `width`
+/// columns, it's truncated if it doesn't fit
+pub fn right_aligned(text: &str, width: usize) -> String {
+    let text = truncate(text, width);
+    " ".repeat(width - self::width(&text)) + &text
+}
+
+/// Drops the chars from the start of given text until the rest starts at
+/// column `column` or later, returns the rest and its starting column
+pub fn skip_columns(text: &str, column: usize) -> (&str, usize) {
+    let mut skipped = 0;
+    for (i, ch) in text.char_indices() {
+        if skipped >= column {
+            return (&text[i..], skipped);
+        }
+        skipped += char_width(ch);
+    }
+
+    ("", skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths() {
+        let cases = &[
+            ("rust", 4),
+            ("ışık", 4),
+            ("東京", 4),
+            ("🎉", 2),
+            // e and a combining acute accent
+            ("e\u{301}", 1),
+            ("", 0),
+        ];
+
+        for &(text, columns) in cases {
+            assert_eq!(width(text), columns, "width of {:?}", text);
+        }
+    }
+
+    #[test]
+    fn truncates() {
+        let cases = &[
+            ("rust", 4, "rust"),
+            ("rust", 5, "rust"),
+            ("rust", 3, "ru…"),
+            ("rust", 1, "…"),
+            ("rust", 0, ""),
+            ("ışık sözlük", 6, "ışık …"),
+            ("東京", 4, "東京"),
+            ("東京都", 5, "東京…"),
+            // A wide char doesn't fit into the column before the ellipsis
+            ("東京都", 4, "東…"),
+            ("🎉🎉🎉", 4, "🎉…"),
+        ];
+
+        for &(text, columns, truncated) in cases {
+            assert_eq!(truncate(text, columns), truncated, "{:?} in {} columns", text, columns);
+        }
+    }
+
+    #[test]
+    fn aligns_right() {
+        let cases = &[
+            ("ab", 4, "  ab"),
+            ("東", 3, " 東"),
+            ("rust", 4, "rust"),
+            ("rust", 3, "ru…"),
+            ("東京都", 4, " 東…"),
+        ];
+
+        for &(text, columns, aligned) in cases {
+            assert_eq!(right_aligned(text, columns), aligned, "{:?} in {} columns", text, columns);
+        }
+    }
+
+    #[test]
+    fn skips_columns() {
+        let cases = &[
+            ("rust", 0, "rust", 0),
+            ("rust", 2, "st", 2),
+            ("ışık", 1, "şık", 1),
+            // Half of a wide char can't be skipped
+            ("東京", 1, "京", 2),
+            ("東京", 2, "京", 2),
+            ("ab", 5, "", 2),
+        ];
+
+        for &(text, column, rest, start) in cases {
+            assert_eq!(skip_columns(text, column), (rest, start), "{:?} from {}", text, column);
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use std::env;
+
+use tui::style::{Color, Modifier, Style};
+
+use eksicli::config::Config;
+
+/// Prefix of the config sections of the user themes, `[theme.<name>]`
+const SECTION_PREFIX: &str = "theme.";
+
+/// Colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSupport {
+    /// Only the modifiers, for `NO_COLOR` and dumb terminals
+    None,
+    /// The 16 named colors, the 256-color terminals too since tui can only
+    /// draw the other colors as 24-bit ones
+    Basic,
+    /// 24-bit colors
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Guesses the color support from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> ColorSupport {
+        if env::var_os("NO_COLOR").is_some() {
+            return ColorSupport::None;
+        }
+
+        let term = env::var("TERM").unwrap_or_default();
+        if term.is_empty() || term == "dumb" {
+            return ColorSupport::None;
+        }
+
+        match env::var("COLORTERM").as_ref().map(|x| x.as_str()) {
+            Ok("truecolor") | Ok("24bit") => ColorSupport::TrueColor,
+            _ => ColorSupport::Basic,
+        }
+    }
+}
+
+/// Named styles of the parts of the ui
+///
+/// A theme is one of the built-in themes (`dark`, `light` and `mono`) or a
+/// user theme from the config file. User themes override the styles of a
+/// built-in theme:
+///
+/// ```text
+/// [ui]
+/// theme = mine
+///
+/// [theme.mine]
+/// base = light
+/// title = fg=#005f87 bold
+/// selection = fg=black bg=214
+/// ```
+///
+/// Colors are names, `#rrggbb` or 256-color indexes. Without 24-bit color
+/// support the colors other than the names become the closest names. A
+/// style can have one modifier: bold, italic, underline, invert or faint.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Channel switcher and the title of a debe entry
+    pub header: Style,
+    /// Title of the open title
+    pub title: Style,
+    /// Number of an entry
+    pub entry_header: Style,
+    pub entry_text: Style,
+    pub author: Style,
+    pub date: Style,
+    /// Selected title, suggestion and search form field
+    pub selection: Style,
+    pub bkz: Style,
+    pub mention: Style,
+    pub link: Style,
+    pub spoiler: Style,
+    /// Matches of `:find`
+    pub matched: Style,
+    pub error: Style,
+    /// The help line
+    pub info: Style,
+    /// The command line
+    pub input: Style,
+    /// Suggestion popup
+    pub popup: Style,
+    /// Indicators and hints
+    pub muted: Style,
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            header: Style::default().fg(Color::Yellow).modifier(Modifier::Bold),
+            title: Style::default().fg(Color::White).modifier(Modifier::Bold),
+            entry_header: Style::default().fg(Color::Magenta),
+            entry_text: Style::default(),
+            author: Style::default().fg(Color::Cyan),
+            date: Style::default().fg(Color::DarkGray),
+            selection: Style::default().fg(Color::Black).bg(Color::Yellow),
+            bkz: Style::default().fg(Color::Green),
+            mention: Style::default().fg(Color::LightCyan),
+            link: Style::default().fg(Color::Blue),
+            spoiler: Style::default().fg(Color::Red),
+            matched: Style::default().fg(Color::Black).bg(Color::LightGreen),
+            error: Style::default().fg(Color::Black).bg(Color::Red),
+            info: Style::default().fg(Color::White).bg(Color::DarkGray),
+            input: Style::default().fg(Color::Black).bg(Color::Yellow),
+            popup: Style::default().fg(Color::Black).bg(Color::White),
+            muted: Style::default().fg(Color::DarkGray),
+        }
+    }
+
+    /// For terminals with light backgrounds, no text is drawn in white, gray
+    /// or yellow over the background
+    pub fn light() -> Theme {
+        Theme {
+            header: Style::default().fg(Color::Blue).modifier(Modifier::Bold),
+            title: Style::default().fg(Color::Black).modifier(Modifier::Bold),
+            entry_header: Style::default().fg(Color::Magenta),
+            entry_text: Style::default(),
+            author: Style::default().fg(Color::Blue),
+            date: Style::default().fg(Color::DarkGray),
+            selection: Style::default().fg(Color::White).bg(Color::Blue),
+            bkz: Style::default().fg(Color::Green),
+            mention: Style::default().fg(Color::Cyan),
+            link: Style::default().fg(Color::Blue),
+            spoiler: Style::default().fg(Color::Red),
+            matched: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            error: Style::default().fg(Color::White).bg(Color::Red),
+            info: Style::default().fg(Color::Black).bg(Color::Gray),
+            input: Style::default().fg(Color::White).bg(Color::Blue),
+            popup: Style::default().fg(Color::Black).bg(Color::Gray),
+            muted: Style::default().fg(Color::DarkGray),
+        }
+    }
+
+    /// Only the modifiers, for `NO_COLOR`
+    pub fn mono() -> Theme {
+        let plain = Style::default();
+        Theme {
+            header: plain.modifier(Modifier::Bold),
+            title: plain.modifier(Modifier::Bold),
+            entry_header: plain.modifier(Modifier::Bold),
+            entry_text: plain,
+            author: plain,
+            date: plain,
+            selection: plain.modifier(Modifier::Invert),
+            bkz: plain.modifier(Modifier::Bold),
+            mention: plain.modifier(Modifier::Bold),
+            link: plain.modifier(Modifier::Bold),
+            spoiler: plain.modifier(Modifier::Italic),
+            matched: plain.modifier(Modifier::Invert),
+            error: plain.modifier(Modifier::Invert),
+            info: plain.modifier(Modifier::Invert),
+            input: plain.modifier(Modifier::Invert),
+            popup: plain.modifier(Modifier::Invert),
+            muted: plain,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "mono" => Some(Theme::mono()),
+            _ => None,
+        }
+    }
+
+    /// Returns the theme named in `[ui] theme`, `dark` by default, adapted to
+    /// the colors of the terminal
+    pub fn from_config(config: &Config, support: ColorSupport) -> Result<Theme, String> {
+        if support == ColorSupport::None {
+            return Ok(Theme::mono());
+        }
+
+        let name = config.get("ui", "theme").unwrap_or("dark");
+        let theme = match Theme::builtin(name) {
+            Some(theme) => theme,
+            None => Theme::user(config, name)?,
+        };
+
+        Ok(theme.adapted(support))
+    }
+
+    /// Reads a user theme from the `[theme.<name>]` section
+    fn user(config: &Config, name: &str) -> Result<Theme, String> {
+        let section = format!("{}{}", SECTION_PREFIX, name);
+        if !config.sections(&section).contains(&section.as_str()) {
+            return Err(format!("Unknown theme: {}", name));
+        }
+
+        let base = config.get(&section, "base").unwrap_or("dark");
+        let mut theme = Theme::builtin(base).ok_or_else(|| format!("Unknown base theme: {}", base))?;
+
+        for key in Theme::KEYS {
+            if let Some(spec) = config.get(&section, key) {
+                let style = parse_style(spec).map_err(|e| format!("{}.{}: {}", section, key, e))?;
+                *theme.style_mut(key).unwrap() = style;
+            }
+        }
+
+        Ok(theme)
+    }
+
+    const KEYS: &'static [&'static str] = &[
+        "header", "title", "entry_header", "entry_text", "author", "date", "selection", "bkz",
+        "mention", "link", "spoiler", "matched", "error", "info", "input", "popup", "muted",
+    ];
+
+    fn style_mut(&mut self, key: &str) -> Option<&mut Style> {
+        let style = match key {
+            "header" => &mut self.header,
+            "title" => &mut self.title,
+            "entry_header" => &mut self.entry_header,
+            "entry_text" => &mut self.entry_text,
+            "author" => &mut self.author,
+            "date" => &mut self.date,
+            "selection" => &mut self.selection,
+            "bkz" => &mut self.bkz,
+            "mention" => &mut self.mention,
+            "link" => &mut self.link,
+            "spoiler" => &mut self.spoiler,
+            "matched" => &mut self.matched,
+            "error" => &mut self.error,
+            "info" => &mut self.info,
+            "input" => &mut self.input,
+            "popup" => &mut self.popup,
+            "muted" => &mut self.muted,
+            _ => return None,
+        };
+
+        Some(style)
+    }
+
+    /// Replaces the 24-bit colors with the closest ones the terminal can
+    /// show
+    fn adapted(mut self, support: ColorSupport) -> Theme {
+        let closest: fn(Color) -> Color = match support {
+            ColorSupport::TrueColor | ColorSupport::None => return self,
+            ColorSupport::Basic => closest_basic,
+        };
+
+        for key in Theme::KEYS {
+            let style = self.style_mut(key).unwrap();
+            style.fg = closest(style.fg);
+            style.bg = closest(style.bg);
+        }
+
+        self
+    }
+}
+
+/// Parses a style like `fg=red bg=#202020 bold`
+fn parse_style(spec: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    for part in spec.split_whitespace() {
+        style = match part.find('=') {
+            Some(i) if &part[..i] == "fg" => style.fg(parse_color(&part[i + 1..])?),
+            Some(i) if &part[..i] == "bg" => style.bg(parse_color(&part[i + 1..])?),
+            Some(_) => return Err(format!("unknown attribute '{}'", part)),
+            None => style.modifier(match part {
+                "bold" => Modifier::Bold,
+                "italic" => Modifier::Italic,
+                "underline" => Modifier::Underline,
+                "invert" | "reverse" => Modifier::Invert,
+                "faint" | "dim" => Modifier::Faint,
+                _ => return Err(format!("unknown modifier '{}'", part)),
+            }),
+        };
+    }
+
+    Ok(style)
+}
+
+/// Named colors with their usual xterm values
+const BASIC_COLORS: &[(&str, Color, (u8, u8, u8))] = &[
+    ("black", Color::Black, (0, 0, 0)),
+    ("red", Color::Red, (205, 0, 0)),
+    ("green", Color::Green, (0, 205, 0)),
+    ("yellow", Color::Yellow, (205, 205, 0)),
+    ("blue", Color::Blue, (0, 0, 238)),
+    ("magenta", Color::Magenta, (205, 0, 205)),
+    ("cyan", Color::Cyan, (0, 205, 205)),
+    ("gray", Color::Gray, (229, 229, 229)),
+    ("darkgray", Color::DarkGray, (127, 127, 127)),
+    ("lightred", Color::LightRed, (255, 0, 0)),
+    ("lightgreen", Color::LightGreen, (0, 255, 0)),
+    ("lightyellow", Color::LightYellow, (255, 255, 0)),
+    ("lightblue", Color::LightBlue, (92, 92, 255)),
+    ("lightmagenta", Color::LightMagenta, (255, 0, 255)),
+    ("lightcyan", Color::LightCyan, (0, 255, 255)),
+    ("white", Color::White, (255, 255, 255)),
+];
+
+/// Parses a color name, `#rrggbb` or a 256-color index
+fn parse_color(spec: &str) -> Result<Color, String> {
+    let name = spec.to_lowercase().replace("_", "").replace("-", "");
+    if name == "reset" || name == "default" {
+        return Ok(Color::Reset);
+    }
+    if let Some(&(_, color, _)) = BASIC_COLORS.iter().find(|x| x.0 == name) {
+        return Ok(color);
+    }
+
+    if name.starts_with('#') && name.len() == 7 {
+        let channel = |i: usize| u8::from_str_radix(&name[i..i + 2], 16);
+        if let (Ok(r), Ok(g), Ok(b)) = (channel(1), channel(3), channel(5)) {
+            return Ok(Color::Rgb(r, g, b));
+        }
+    }
+
+    match name.parse::<u8>() {
+        Ok(index) => Ok(indexed(index)),
+        Err(_) => Err(format!("unknown color '{}'", spec)),
+    }
+}
+
+/// Returns the color of given index of the xterm 256-color palette
+fn indexed(index: u8) -> Color {
+    match index {
+        // Same order as the palette
+        0..=15 => BASIC_COLORS[index as usize].1,
+        16..=231 => {
+            let level = |x: u8| if x == 0 { 0 } else { 55 + x * 40 };
+            let i = index - 16;
+            Color::Rgb(level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Color::Rgb(gray, gray, gray)
+        }
+    }
+}
+
+/// Returns the squared distance of two colors
+fn distance((r, g, b): (u8, u8, u8), (x, y, z): (u8, u8, u8)) -> i32 {
+    let (dr, dg, db) = (r as i32 - x as i32, g as i32 - y as i32, b as i32 - z as i32);
+    dr * dr + dg * dg + db * db
+}
+
+/// Returns the closest named color to a 24-bit color
+fn closest_basic(color: Color) -> Color {
+    let rgb = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => return color,
+    };
+
+    BASIC_COLORS.iter()
+        .min_by_key(|x| distance(rgb, x.2))
+        .map_or(color, |x| x.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        let cases = &[
+            ("red", Ok(Color::Red)),
+            ("Light_Blue", Ok(Color::LightBlue)),
+            ("dark-gray", Ok(Color::DarkGray)),
+            ("default", Ok(Color::Reset)),
+            ("#005f87", Ok(Color::Rgb(0, 95, 135))),
+            ("#FFFFFF", Ok(Color::Rgb(255, 255, 255))),
+            ("9", Ok(Color::LightRed)),
+            ("16", Ok(Color::Rgb(0, 0, 0))),
+            ("214", Ok(Color::Rgb(255, 175, 0))),
+            ("255", Ok(Color::Rgb(238, 238, 238))),
+            ("256", Err("unknown color '256'".to_string())),
+            ("#12345", Err("unknown color '#12345'".to_string())),
+            ("#gggggg", Err("unknown color '#gggggg'".to_string())),
+            ("purple", Err("unknown color 'purple'".to_string())),
+            ("", Err("unknown color ''".to_string())),
+        ];
+
+        for &(spec, ref expected) in cases {
+            assert_eq!(parse_color(spec), *expected, "color {:?}", spec);
+        }
+    }
+
+    #[test]
+    fn parses_styles() {
+        let plain = Style::default();
+        let cases = &[
+            ("", Ok(plain)),
+            ("fg=red bg=#202020 bold", Ok(plain.fg(Color::Red).bg(Color::Rgb(32, 32, 32)).modifier(Modifier::Bold))),
+            ("  reverse ", Ok(plain.modifier(Modifier::Invert))),
+            ("dim fg=blue", Ok(plain.fg(Color::Blue).modifier(Modifier::Faint))),
+            ("fg=nope", Err("unknown color 'nope'".to_string())),
+            ("bg=", Err("unknown color ''".to_string())),
+            ("color=red", Err("unknown attribute 'color=red'".to_string())),
+            ("bold blink", Err("unknown modifier 'blink'".to_string())),
+        ];
+
+        for &(spec, ref expected) in cases {
+            assert_eq!(parse_style(spec), *expected, "style {:?}", spec);
+        }
+    }
+}